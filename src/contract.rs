@@ -1,19 +1,49 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult,
+    coins, from_binary, to_binary, to_vec, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps,
+    DepsMut, Env, Event, MessageInfo, Order, OverflowError, OverflowOperation, Response, StdError,
+    StdResult, Storage, Uint128, WasmMsg,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, TokenInfoResponse};
+use cw4::Cw4Contract;
+use cw_storage_plus::Bound;
+use cw_utils::{may_pay, nonpayable, Expiration, Scheduled};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 
 use crate::error::ContractError;
+use crate::migrations;
 use crate::msg::{
-    AllPollsResponse, ExecuteMsg, InstantiateMsg, PollResponse, QueryMsg, VoteResponse,
+    AllPollsResponse, BallotInfo, ConfigResponse, CreatePollResponse, DelegationResponse,
+    EligibleResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, OrderBy, PollInfo, PollOutcome,
+    PollResponse, PollResultResponse, QueryMsg, RankedResultResponse, RankedRound, ReceiveMsg,
+    VoteResponse, VotesResponse,
 };
-use crate::state::{Ballot, Config, Poll, BALLOTS, CONFIG, POLLS};
+use crate::state::{
+    polls, sequence_poll_id, Ballot, Choice, Config, DelegatedVote, Poll, PollKind, PollLimits,
+    PollOption, PollStatus, RankedTally, Requirement, VotingPower, ALLOWLIST, BALLOTS, CONFIG,
+    DELEGATED_VOTES, DELEGATE_VOTES, DELEGATIONS, DELEGATORS, DELEGATOR_VOTES, PENDING_ADMIN,
+    POLL_BALLOTS, POLL_COUNT, POLL_DELEGATIONS, POLL_DELEGATORS, RANKED_TALLIES,
+};
+use crate::validation;
 
 const CONTRACT_NAME: &str = "crates.io:cw-poll-ballots";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Pagination defaults for the list queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+// The most polls AllPolls reads while looking for ones with the requested status
+const MAX_SCAN: usize = 100;
+// Ballots TallyRanked counts per call
+const DEFAULT_TALLY_LIMIT: u32 = 100;
+const MAX_TALLY_LIMIT: u32 = 500;
+// The most delegators a delegate can have globally, and again on each poll, so that
+// picking up their weight cannot make the delegate's vote run out of gas
+const MAX_DELEGATORS: usize = 50;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -25,9 +55,13 @@ pub fn instantiate(
     let admin = msg.admin.unwrap_or_else(|| info.sender.to_string()); // if None, use info.sender
     let validated_admin = deps.api.addr_validate(&admin)?; // validate the address
     let config = Config {
-        admin: validated_admin.clone(),
+        admin: Some(validated_admin.clone()),
+        limits: PollLimits::default(),
+        deposit: None,
+        paused: false,
     };
     CONFIG.save(deps.storage, &config)?;
+    POLL_COUNT.save(deps.storage, &0)?;
     Ok(Response::new()
         .add_attribute("action", "instantiate")
         .add_attribute("admin", validated_admin.to_string()))
@@ -45,193 +79,4578 @@ pub fn execute(
             poll_id,
             question,
             options,
-        } => execute_create_poll(deps, env, info, poll_id, question, options),
+            start,
+            end,
+            voting_power,
+            kind,
+            voters,
+            quorum,
+            threshold,
+            msgs,
+            reveal_end,
+        } => execute_create_poll(
+            deps,
+            env,
+            info,
+            poll_id,
+            question,
+            options,
+            start,
+            end,
+            voting_power,
+            kind,
+            voters,
+            quorum,
+            threshold,
+            msgs,
+            reveal_end,
+        ),
         ExecuteMsg::Vote { poll_id, vote } => execute_vote(deps, env, info, poll_id, vote),
+        ExecuteMsg::Reveal {
+            poll_id,
+            vote,
+            salt,
+        } => execute_reveal(deps, env, info, poll_id, vote, salt),
+        ExecuteMsg::Receive(wrapper) => execute_receive(deps, env, info, wrapper),
+        ExecuteMsg::Withdraw { poll_id } => execute_withdraw(deps, env, info, poll_id),
+        ExecuteMsg::ClosePoll { poll_id } => execute_close_poll(deps, env, info, poll_id),
+        ExecuteMsg::DeletePoll { poll_id } => execute_delete_poll(deps, env, info, poll_id),
+        ExecuteMsg::TallyRanked { poll_id, limit } => {
+            execute_tally_ranked(deps, env, info, poll_id, limit)
+        }
+        ExecuteMsg::Execute { poll_id } => execute_execute(deps, env, info, poll_id),
+        ExecuteMsg::AddVoters { poll_id, voters } => {
+            execute_add_voters(deps, env, info, poll_id, voters)
+        }
+        ExecuteMsg::RemoveVoters { poll_id, voters } => {
+            execute_remove_voters(deps, env, info, poll_id, voters)
+        }
+        ExecuteMsg::UpdatePoll {
+            poll_id,
+            question,
+            options,
+        } => execute_update_poll(deps, env, info, poll_id, question, options),
+        ExecuteMsg::Delegate { to, poll_id } => execute_delegate(deps, env, info, to, poll_id),
+        ExecuteMsg::Undelegate { poll_id } => execute_undelegate(deps, env, info, poll_id),
+        ExecuteMsg::UpdateLimits { limits } => execute_update_limits(deps, env, info, limits),
+        ExecuteMsg::UpdateDeposit { deposit } => execute_update_deposit(deps, env, info, deposit),
+        ExecuteMsg::ClaimDeposit { poll_id } => execute_claim_deposit(deps, env, info, poll_id),
+        ExecuteMsg::FlagSpam { poll_id } => execute_flag_spam(deps, env, info, poll_id),
+        ExecuteMsg::ProposeNewAdmin { new_admin } => {
+            execute_propose_new_admin(deps, env, info, new_admin)
+        }
+        ExecuteMsg::AcceptAdmin {} => execute_accept_admin(deps, env, info),
+        ExecuteMsg::RenounceAdmin {} => execute_renounce_admin(deps, env, info),
+        ExecuteMsg::Pause {} => execute_set_paused(deps, env, info, true),
+        ExecuteMsg::Unpause {} => execute_set_paused(deps, env, info, false),
+        ExecuteMsg::FreezePoll { poll_id } => execute_set_frozen(deps, env, info, poll_id, true),
+        ExecuteMsg::UnfreezePoll { poll_id } => execute_set_frozen(deps, env, info, poll_id, false),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn execute_create_poll(
     deps: DepsMut,
-    _env: Env, // _env as we won't be using it
+    env: Env,
     info: MessageInfo,
-    poll_id: String,
+    poll_id: Option<String>,
     question: String,
     options: Vec<String>,
+    start: Option<Scheduled>,
+    end: Option<Expiration>,
+    voting_power: Option<VotingPower>,
+    kind: Option<PollKind>,
+    voters: Option<Vec<String>>,
+    quorum: Option<Requirement>,
+    threshold: Option<Requirement>,
+    msgs: Option<Vec<Vec<CosmosMsg>>>,
+    reveal_end: Option<Expiration>,
 ) -> Result<Response, ContractError> {
-    if POLLS.has(deps.storage, &poll_id) {
+    let config = CONFIG.load(deps.storage)?;
+    if config.paused {
+        return Err(ContractError::Paused {});
+    }
+    let poll_id = match poll_id {
+        Some(poll_id) => {
+            validation::validate_poll_id(&poll_id, &config.limits)?;
+            poll_id
+        }
+        None => next_poll_id(deps.storage)?,
+    };
+    if polls().has(deps.storage, &poll_id) {
         return Err(ContractError::PollExisted {});
     }
+    let question = validation::normalize_question(&question, &config.limits)?;
+    let options = validation::normalize_options(&options, &config.limits)?;
+
+    // The deposit is held until the poll ends, then refunded or forfeited as spam
+    match &config.deposit {
+        Some(required) => {
+            if may_pay(&info, &required.denom)? != required.amount {
+                return Err(ContractError::InvalidDeposit {
+                    expected: required.to_string(),
+                });
+            }
+        }
+        None => nonpayable(&info)?,
+    }
+
+    if let Some(end) = end {
+        // A poll that is closed on creation can never be voted on
+        if end.is_expired(&env.block) {
+            return Err(ContractError::InvalidPollPeriod {});
+        }
+        // Only heights can be compared with heights and times with times
+        let ends_before_start = match (start, end) {
+            (Some(Scheduled::AtHeight(start)), Expiration::AtHeight(end)) => end <= start,
+            (Some(Scheduled::AtTime(start)), Expiration::AtTime(end)) => end <= start,
+            _ => false,
+        };
+        if ends_before_start {
+            return Err(ContractError::InvalidPollPeriod {});
+        }
+    }
+
+    if let Some(reveal_end) = reveal_end {
+        // Secret polls need a voting period to end before votes can be revealed
+        let reveals_before_end = match (end, reveal_end) {
+            (None, _) => true,
+            (Some(Expiration::AtHeight(end)), Expiration::AtHeight(reveal_end)) => {
+                reveal_end <= end
+            }
+            (Some(Expiration::AtTime(end)), Expiration::AtTime(reveal_end)) => reveal_end <= end,
+            _ => false,
+        };
+        if reveals_before_end || reveal_end.is_expired(&env.block) {
+            return Err(ContractError::InvalidPollPeriod {});
+        }
+    }
 
-    if options.len() > 10 {
-        return Err(ContractError::TooManyOptions {});
+    let (voting_power, group_weight) = match voting_power.unwrap_or_default() {
+        VotingPower::Cw20Token { address } => {
+            let address = deps.api.addr_validate(address.as_str())?;
+            (VotingPower::Cw20Token { address }, None)
+        }
+        VotingPower::Cw4Group { address } => {
+            let address = deps.api.addr_validate(address.as_str())?;
+            // Make sure it answers like a group, and keep the total that goes with the
+            // membership snapshot ballots are weighed at
+            let total = Cw4Contract::new(address.clone()).total_weight(&deps.querier)?;
+            (
+                VotingPower::Cw4Group { address },
+                Some(Uint128::from(total)),
+            )
+        }
+        voting_power => (voting_power, None),
+    };
+
+    let kind = kind.unwrap_or_default();
+    validation::validate_kind(&kind, options.len())?;
+
+    // Supplying a list of voters, even an empty one, restricts the poll to them
+    let allowlisted = voters.is_some();
+    let voters = voters
+        .unwrap_or_default()
+        .iter()
+        .map(|voter| deps.api.addr_validate(voter))
+        .collect::<StdResult<Vec<_>>>()?;
+    for requirement in quorum.iter().chain(threshold.iter()) {
+        if let Requirement::Percentage(pct) = requirement {
+            if *pct > Decimal::one() {
+                return Err(ContractError::InvalidPercentage {});
+            }
+        }
+    }
+    // A quorum percentage needs to know how much voting power exists
+    if let Some(Requirement::Percentage(_)) = quorum {
+        let known = match voting_power {
+            VotingPower::Equal => allowlisted,
+            VotingPower::NativeToken { .. } => false,
+            VotingPower::Cw20Token { .. } | VotingPower::Cw4Group { .. } => true,
+        };
+        if !known {
+            return Err(ContractError::UnknownEligibleWeight {});
+        }
+    }
+
+    for voter in &voters {
+        ALLOWLIST.save(deps.storage, (&poll_id, voter.clone()), &())?;
     }
 
-    let mut opts: Vec<(String, u64)> = vec![];
-    for option in options {
-        opts.push((option, 0));
+    let mut opts: Vec<PollOption> = options.into_iter().map(PollOption::new).collect();
+    if let Some(msgs) = msgs {
+        // Messages run with the contract's funds, so only the admin may attach them
+        if !config.is_admin(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        if msgs.len() != opts.len() {
+            return Err(ContractError::InvalidMsgs {});
+        }
+        for (option, msgs) in opts.iter_mut().zip(msgs) {
+            option.msgs = msgs;
+        }
     }
 
     let poll = Poll {
         creator: info.sender,
         question,
         options: opts,
+        start,
+        end,
+        voting_power,
+        kind,
+        allowlisted,
+        created_height: env.block.height,
+        quorum,
+        threshold,
+        turnout: Uint128::zero(),
+        executed: false,
+        reveal_end,
+        deposit: config.deposit,
+        frozen: false,
+        ballot_count: 0,
+        group_weight,
     };
 
-    POLLS.save(deps.storage, &poll_id, &poll)?;
+    polls().save(deps.storage, &poll_id, &poll)?;
 
-    Ok(Response::new())
+    // Enough for an indexer to rebuild the poll from the logs, anything more than
+    // a plain string or number is logged as JSON
+    let mut event = Event::new("create_poll")
+        .add_attribute("poll_id", &poll_id)
+        .add_attribute("creator", &poll.creator)
+        .add_attribute("question", &poll.question);
+    for option in &poll.options {
+        event = event
+            .add_attribute("option", &option.text)
+            .add_attribute("msgs", json_string(&option.msgs)?);
+    }
+    event = event
+        .add_attribute("start", json_string(&poll.start)?)
+        .add_attribute("end", json_string(&poll.end)?)
+        .add_attribute("voting_power", json_string(&poll.voting_power)?)
+        .add_attribute("kind", json_string(&poll.kind)?)
+        .add_attribute("allowlisted", poll.allowlisted.to_string());
+    for voter in &voters {
+        event = event.add_attribute("voter", voter);
+    }
+    event = event
+        .add_attribute("created_height", poll.created_height.to_string())
+        .add_attribute("quorum", json_string(&poll.quorum)?)
+        .add_attribute("threshold", json_string(&poll.threshold)?)
+        .add_attribute("reveal_end", json_string(&poll.reveal_end)?)
+        .add_attribute("deposit", json_string(&poll.deposit)?)
+        .add_attribute("group_weight", json_string(&poll.group_weight)?);
+    Ok(Response::new()
+        .add_attribute("action", "create_poll")
+        .add_attribute("poll_id", &poll_id)
+        .add_attribute("creator", poll.creator.clone())
+        .add_event(event)
+        .set_data(to_binary(&CreatePollResponse { poll_id })?))
 }
 
-fn execute_vote(
+// Take the next number from the sequence, skipping any a client already chose as an ID
+fn next_poll_id(storage: &mut dyn Storage) -> StdResult<String> {
+    let mut count = POLL_COUNT.may_load(storage)?.unwrap_or_default();
+    let poll_id = loop {
+        count = count
+            .checked_add(1)
+            .ok_or_else(|| OverflowError::new(OverflowOperation::Add, count, 1))?;
+        let poll_id = sequence_poll_id(count);
+        if !polls().has(storage, &poll_id) {
+            break poll_id;
+        }
+    };
+    POLL_COUNT.save(storage, &count)?;
+    Ok(poll_id)
+}
+
+fn execute_update_poll(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     poll_id: String,
-    vote: String,
+    question: Option<String>,
+    options: Option<Vec<String>>,
 ) -> Result<Response, ContractError> {
-    if !POLLS.has(deps.storage, &poll_id) {
-        return Err(ContractError::PollNotExisted {});
+    let mut poll = polls()
+        .may_load(deps.storage, &poll_id)?
+        .ok_or(ContractError::PollNotExisted)?;
+    if info.sender != poll.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+    // Once someone voted the poll must stay as they saw it
+    if poll.ballot_count > 0 {
+        return Err(ContractError::PollHasVotes {});
     }
 
-    let poll = POLLS.may_load(deps.storage, &poll_id)?;
+    let limits = CONFIG.load(deps.storage)?.limits;
+    if let Some(question) = question {
+        poll.question = validation::normalize_question(&question, &limits)?;
+    }
+    if let Some(options) = options {
+        let options = validation::normalize_options(&options, &limits)?;
+        validation::validate_kind(&poll.kind, options.len())?;
+        // Messages are tied to positions, so are only kept while the count is unchanged
+        let has_msgs = poll.options.iter().any(|option| !option.msgs.is_empty());
+        if has_msgs && options.len() != poll.options.len() {
+            return Err(ContractError::InvalidMsgs {});
+        }
+        let mut old_options = poll.options.into_iter();
+        poll.options = options
+            .into_iter()
+            .map(|text| PollOption {
+                msgs: old_options
+                    .next()
+                    .map(|option| option.msgs)
+                    .unwrap_or_default(),
+                ..PollOption::new(text)
+            })
+            .collect();
+    }
+    polls().save(deps.storage, &poll_id, &poll)?;
 
-    match poll {
-        Some(mut poll) => {
-            // The poll exists
-            BALLOTS.update(
-                deps.storage,
-                (info.sender, &poll_id),
-                |ballot| -> StdResult<Ballot> {
-                    match ballot {
-                        Some(ballot) => {
-                            // We need to revoke their old vote
-                            // Find the position
-                            let position_of_old_vote = poll
-                                .options
-                                .iter()
-                                .position(|option| option.0 == ballot.option)
-                                .unwrap();
-                            // Decrement by 1
-                            poll.options[position_of_old_vote].1 -= 1;
-                            // Update the ballot
-                            Ok(Ballot {
-                                option: vote.clone(),
-                            })
-                        }
-                        None => {
-                            // Simply add the ballot
-                            Ok(Ballot {
-                                option: vote.clone(),
-                            })
-                        }
-                    }
-                },
-            )?;
+    // Logged like create_poll so an indexer can overwrite what it stored then
+    let mut event = Event::new("update_poll")
+        .add_attribute("poll_id", &poll_id)
+        .add_attribute("creator", &poll.creator)
+        .add_attribute("question", &poll.question);
+    for option in &poll.options {
+        event = event
+            .add_attribute("option", &option.text)
+            .add_attribute("msgs", json_string(&option.msgs)?);
+    }
+    Ok(Response::new()
+        .add_attribute("action", "update_poll")
+        .add_attribute("poll_id", poll_id)
+        .add_event(event))
+}
+
+fn execute_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    vote: Choice,
+) -> Result<Response, ContractError> {
+    let poll = polls()
+        .may_load(deps.storage, &poll_id)?
+        .ok_or(ContractError::PollNotExisted)?;
+
+    // Native funds sent along are locked as voting power, CW20 tokens come in through Receive.
+    // Any other poll has nothing to lock them against, so they are refused
+    let deposit = match &poll.voting_power {
+        VotingPower::NativeToken { denom } => may_pay(&info, denom)?,
+        _ => {
+            nonpayable(&info)?;
+            Uint128::zero()
+        }
+    };
+
+    cast_vote(deps, env, info.sender, poll_id, poll, vote, deposit)
+}
 
-            // Find the position of the new vote option and increment it by 1
-            let position = poll.options.iter().position(|option| option.0 == vote);
-            if position.is_none() {
-                return Err(ContractError::Unauthorized {});
+fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let voter = deps.api.addr_validate(&wrapper.sender)?;
+    match from_binary(&wrapper.msg)? {
+        ReceiveMsg::Vote { poll_id, vote } => {
+            let poll = polls()
+                .may_load(deps.storage, &poll_id)?
+                .ok_or(ContractError::PollNotExisted)?;
+
+            // info.sender is the token contract that called us
+            match &poll.voting_power {
+                VotingPower::Cw20Token { address } if *address == info.sender => {}
+                _ => return Err(ContractError::InvalidToken {}),
             }
-            let position = position.unwrap();
-            poll.options[position].1 += 1;
 
-            // Save the update
-            POLLS.save(deps.storage, &poll_id, &poll)?;
-            Ok(Response::new())
+            cast_vote(deps, env, voter, poll_id, poll, vote, wrapper.amount)
         }
-        None => Err(ContractError::Unauthorized {}), // The poll does not exist so we just error
     }
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::AllPolls {} => query_all_polls(deps, env),
-        QueryMsg::Poll { poll_id } => query_poll(deps, env, poll_id),
-        QueryMsg::Vote { address, poll_id } => query_vote(deps, env, address, poll_id),
+fn cast_vote(
+    deps: DepsMut,
+    env: Env,
+    voter: Addr,
+    poll_id: String,
+    mut poll: Poll,
+    vote: Choice,
+    deposit: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_votes_allowed(deps.storage, &poll)?;
+
+    // Make sure the poll is accepting votes
+    match poll.status(&env.block) {
+        PollStatus::Pending => return Err(ContractError::PollNotStarted {}),
+        PollStatus::Revealing | PollStatus::Closed | PollStatus::Executed => {
+            return Err(ContractError::PollClosed {})
+        }
+        PollStatus::Open => {}
     }
+
+    // Secret polls only take sealed votes and open ones never do
+    if poll.reveal_end.is_some() != matches!(vote, Choice::Sealed { .. }) {
+        return Err(ContractError::InvalidChoice {});
+    }
+
+    if poll.allowlisted && !ALLOWLIST.has(deps.storage, (&poll_id, voter.clone())) {
+        return Err(ContractError::NotEligible {});
+    }
+
+    let old_ballot = BALLOTS.may_load(deps.storage, (voter.clone(), &poll_id))?;
+
+    // Work out how much the ballot counts for
+    let mut weight = match &poll.voting_power {
+        VotingPower::Equal => Uint128::new(1),
+        VotingPower::NativeToken { .. } | VotingPower::Cw20Token { .. } => {
+            // Tokens stay locked in the contract and add to any earlier deposit
+            let locked = old_ballot
+                .as_ref()
+                .map(|ballot| ballot.weight)
+                .unwrap_or_default();
+            locked + deposit
+        }
+        VotingPower::Cw4Group { address } => {
+            // Membership is read as of the poll's creation so it cannot be changed mid vote
+            let weight = Cw4Contract::new(address.clone())
+                .member_at_height(&deps.querier, voter.clone(), Some(poll.created_height))?
+                .ok_or(ContractError::NotEligible {})?;
+            Uint128::from(weight)
+        }
+    };
+    if weight.is_zero() {
+        return Err(ContractError::NoVotingPower {});
+    }
+
+    // Voting directly overrides a delegate who already cast this voter's weight
+    let revoked = revoke_delegated_vote(deps.storage, &poll_id, &mut poll, &voter)?;
+
+    // Delegations are picked up afresh every time the delegate votes
+    let previously_delegated = DELEGATE_VOTES
+        .prefix((&poll_id, voter.clone()))
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for delegator in previously_delegated {
+        DELEGATED_VOTES.remove(deps.storage, (&poll_id, delegator.clone()));
+        DELEGATOR_VOTES.remove(deps.storage, (delegator.clone(), &poll_id));
+        DELEGATE_VOTES.remove(deps.storage, (&poll_id, voter.clone(), delegator));
+    }
+    for (delegator, delegated) in delegated_weights(deps.as_ref(), &poll_id, &poll, &voter)? {
+        weight += delegated;
+        let delegated = DelegatedVote {
+            delegate: voter.clone(),
+            weight: delegated,
+        };
+        DELEGATED_VOTES.save(deps.storage, (&poll_id, delegator.clone()), &delegated)?;
+        DELEGATOR_VOTES.save(deps.storage, (delegator.clone(), &poll_id), &())?;
+        DELEGATE_VOTES.save(deps.storage, (&poll_id, voter.clone(), delegator), &())?;
+    }
+
+    // We need to revoke their old vote from every option it counted towards
+    let mut previous_option = String::new();
+    if old_ballot.is_none() {
+        poll.ballot_count += 1;
+    }
+    if let Some(ballot) = old_ballot {
+        untally(&mut poll, &ballot.option, ballot.weight)?;
+        previous_option = json_string(&ballot.option)?;
+    }
+    tally(&mut poll, &vote, weight)?;
+
+    // Save the ballot and mirror it in the per poll index
+    let ballot = Ballot {
+        option: vote,
+        weight,
+        withdrawn: false,
+    };
+    BALLOTS.save(deps.storage, (voter.clone(), &poll_id), &ballot)?;
+    POLL_BALLOTS.save(deps.storage, (&poll_id, voter.clone()), &ballot)?;
+
+    // Save the update
+    polls().save(deps.storage, &poll_id, &poll)?;
+
+    // Choices are logged as the JSON they were sent in
+    let event = Event::new("vote")
+        .add_attribute("poll_id", &poll_id)
+        .add_attribute("voter", &voter)
+        .add_attribute("option", json_string(&ballot.option)?)
+        .add_attribute("previous_option", previous_option)
+        .add_attribute("weight", ballot.weight)
+        .add_attribute("tallies", tallies_json(&poll)?)
+        .add_attribute("turnout", poll.turnout);
+    Ok(Response::new()
+        .add_attribute("action", "vote")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("voter", voter)
+        .add_events(revoked)
+        .add_event(event))
 }
 
-fn query_all_polls(deps: Deps, _env: Env) -> StdResult<Binary> {
-    let polls = POLLS
-        .range(deps.storage, None, None, Order::Ascending)
-        .map(|p| Ok(p?.1))
+fn json_string<T: Serialize>(value: &T) -> StdResult<String> {
+    Ok(String::from_utf8(to_vec(value)?)?)
+}
+
+// The poll's tallies as JSON [option, votes] pairs
+fn tallies_json(poll: &Poll) -> StdResult<String> {
+    let tallies: Vec<(&String, Uint128)> = poll
+        .options
+        .iter()
+        .map(|option| (&option.text, option.votes))
+        .collect();
+    json_string(&tallies)
+}
+
+// Tallies stop changing while the whole contract is paused or the poll is frozen.
+// A freeze only holds while there is an admin who could lift it
+fn ensure_votes_allowed(storage: &dyn Storage, poll: &Poll) -> Result<(), ContractError> {
+    let config = CONFIG.load(storage)?;
+    if config.paused {
+        return Err(ContractError::Paused {});
+    }
+    if poll.frozen && config.admin.is_some() {
+        return Err(ContractError::PollFrozen {});
+    }
+    Ok(())
+}
+
+// Add the weight to every option the vote selects
+fn tally(poll: &mut Poll, vote: &Choice, weight: Uint128) -> Result<(), ContractError> {
+    // Sealed votes are only counted once they are revealed
+    if matches!(vote, Choice::Sealed { .. }) {
+        return Ok(());
+    }
+    for (position, votes) in counted_votes(poll, vote, weight)? {
+        poll.options[position].votes += votes;
+    }
+    poll.turnout += weight;
+    Ok(())
+}
+
+// Take off what a vote was counted with
+fn untally(poll: &mut Poll, vote: &Choice, weight: Uint128) -> Result<(), ContractError> {
+    if matches!(vote, Choice::Sealed { .. }) {
+        return Ok(());
+    }
+    for (position, votes) in counted_votes(poll, vote, weight)? {
+        poll.options[position].votes -= votes;
+    }
+    poll.turnout -= weight;
+    Ok(())
+}
+
+// The voting power handed to a delegate on a poll by addresses that have not voted themselves
+fn delegated_weights(
+    deps: Deps,
+    poll_id: &str,
+    poll: &Poll,
+    delegate: &Addr,
+) -> StdResult<Vec<(Addr, Uint128)>> {
+    // Deposited tokens stay locked against the ballot that sent them, so cannot be delegated
+    if !matches!(
+        poll.voting_power,
+        VotingPower::Equal | VotingPower::Cw4Group { .. }
+    ) {
+        return Ok(vec![]);
+    }
+
+    // Delegate refuses delegators past MAX_DELEGATORS, the take only guards older state
+    let for_poll = POLL_DELEGATORS
+        .prefix((poll_id, delegate.clone()))
+        .keys(deps.storage, None, None, Order::Ascending)
+        .take(MAX_DELEGATORS)
+        .collect::<StdResult<Vec<_>>>()?;
+    // A delegation for this poll takes precedence over a global one
+    let global = DELEGATORS
+        .prefix(delegate.clone())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .take(MAX_DELEGATORS)
+        .filter(|item| {
+            item.as_ref().map_or(true, |delegator| {
+                !POLL_DELEGATIONS.has(deps.storage, (poll_id, delegator.clone()))
+            })
+        })
         .collect::<StdResult<Vec<_>>>()?;
 
-    to_binary(&AllPollsResponse { polls })
+    let mut weights = vec![];
+    for delegator in for_poll.into_iter().chain(global) {
+        // Leave out anyone who voted themselves or whose weight another delegate already cast
+        if BALLOTS.has(deps.storage, (delegator.clone(), poll_id))
+            || DELEGATED_VOTES.has(deps.storage, (poll_id, delegator.clone()))
+        {
+            continue;
+        }
+        if poll.allowlisted && !ALLOWLIST.has(deps.storage, (poll_id, delegator.clone())) {
+            continue;
+        }
+        let weight = match &poll.voting_power {
+            VotingPower::Cw4Group { address } => Cw4Contract::new(address.clone())
+                .member_at_height(&deps.querier, delegator.clone(), Some(poll.created_height))?
+                .map(Uint128::from)
+                .unwrap_or_default(),
+            _ => Uint128::new(1),
+        };
+        if !weight.is_zero() {
+            weights.push((delegator, weight));
+        }
+    }
+    Ok(weights)
 }
 
-fn query_poll(deps: Deps, _env: Env, poll_id: String) -> StdResult<Binary> {
-    let poll = POLLS.may_load(deps.storage, &poll_id)?;
-    to_binary(&PollResponse { poll })
+// Take a delegator's weight back out of their delegate's ballot, with an event
+// logging the delegate's new weight and the tallies
+fn revoke_delegated_vote(
+    storage: &mut dyn Storage,
+    poll_id: &str,
+    poll: &mut Poll,
+    delegator: &Addr,
+) -> Result<Option<Event>, ContractError> {
+    let delegated = match DELEGATED_VOTES.may_load(storage, (poll_id, delegator.clone()))? {
+        Some(delegated) => delegated,
+        None => return Ok(None),
+    };
+    DELEGATED_VOTES.remove(storage, (poll_id, delegator.clone()));
+    DELEGATOR_VOTES.remove(storage, (delegator.clone(), poll_id));
+    DELEGATE_VOTES.remove(
+        storage,
+        (poll_id, delegated.delegate.clone(), delegator.clone()),
+    );
+
+    let mut ballot = BALLOTS.load(storage, (delegated.delegate.clone(), poll_id))?;
+    untally(poll, &ballot.option, ballot.weight)?;
+    ballot.weight -= delegated.weight;
+    tally(poll, &ballot.option, ballot.weight)?;
+    BALLOTS.save(storage, (delegated.delegate.clone(), poll_id), &ballot)?;
+    POLL_BALLOTS.save(storage, (poll_id, delegated.delegate.clone()), &ballot)?;
+
+    let event = Event::new("revoke_delegation")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("delegate", delegated.delegate)
+        .add_attribute("delegator", delegator)
+        .add_attribute("weight", ballot.weight)
+        .add_attribute("tallies", tallies_json(poll)?)
+        .add_attribute("turnout", poll.turnout);
+    Ok(Some(event))
 }
 
-fn query_vote(deps: Deps, _env: Env, address: String, poll_id: String) -> StdResult<Binary> {
-    let validated_address = deps.api.addr_validate(&address).unwrap();
-    let vote = BALLOTS.may_load(deps.storage, (validated_address, &poll_id))?;
+// Take a delegator's weight back from a delegate they no longer hand it to,
+// while the poll is still open. Closed polls keep the tally they ended with
+fn release_delegated_vote(
+    storage: &mut dyn Storage,
+    env: &Env,
+    poll_id: &str,
+    delegator: &Addr,
+    delegate: &Addr,
+) -> Result<Option<Event>, ContractError> {
+    match DELEGATED_VOTES.may_load(storage, (poll_id, delegator.clone()))? {
+        Some(delegated) if delegated.delegate == *delegate => {}
+        _ => return Ok(None),
+    }
+    let mut poll = polls().load(storage, poll_id)?;
+    if poll.status(&env.block) != PollStatus::Open {
+        return Ok(None);
+    }
+    ensure_votes_allowed(storage, &poll)?;
+    let event = revoke_delegated_vote(storage, poll_id, &mut poll, delegator)?;
+    polls().save(storage, poll_id, &poll)?;
+    Ok(event)
+}
 
-    to_binary(&VoteResponse { vote })
+// The same for every poll a global delegation was counted in. Polls with their own
+// delegation to the same delegate are left alone
+fn release_delegated_votes(
+    storage: &mut dyn Storage,
+    env: &Env,
+    delegator: &Addr,
+    delegate: &Addr,
+) -> Result<Vec<Event>, ContractError> {
+    let poll_ids = DELEGATOR_VOTES
+        .prefix(delegator.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut events = vec![];
+    for poll_id in poll_ids {
+        let for_poll = POLL_DELEGATIONS.may_load(storage, (&poll_id, delegator.clone()))?;
+        if for_poll.as_ref() == Some(delegate) {
+            continue;
+        }
+        events.extend(release_delegated_vote(
+            storage, env, &poll_id, delegator, delegate,
+        )?);
+    }
+    Ok(events)
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::contract::{instantiate, query}; // the contract instantiate function
-    use crate::msg::{
-        AllPollsResponse, ExecuteMsg, InstantiateMsg, PollResponse, QueryMsg, VoteResponse,
+// The positions of the options a vote selects and how many votes each gets,
+// checked against the kind of poll
+fn selected_options(poll: &Poll, vote: &Choice) -> Result<Vec<(usize, u64)>, ContractError> {
+    let selections: Vec<(&String, u64)> = match (&poll.kind, vote) {
+        (_, Choice::Single(option)) => vec![(option, 1)],
+        (PollKind::Approval { max_choices }, Choice::Multiple(options)) => {
+            if options.is_empty() {
+                return Err(ContractError::InvalidChoice {});
+            }
+            if options.len() > *max_choices as usize {
+                return Err(ContractError::TooManyChoices {});
+            }
+            options.iter().map(|option| (option, 1)).collect()
+        }
+        // A ranking may leave options out but must list at least one
+        (PollKind::Ranked, Choice::Multiple(options)) => {
+            if options.is_empty() {
+                return Err(ContractError::InvalidChoice {});
+            }
+            options.iter().map(|option| (option, 1)).collect()
+        }
+        // Casting n votes on an option costs n² credits
+        (PollKind::Quadratic { credits }, Choice::Allocations(allocations)) => {
+            if allocations.is_empty() || allocations.iter().any(|a| a.votes == 0) {
+                return Err(ContractError::InvalidChoice {});
+            }
+            let cost = allocations.iter().try_fold(0u128, |cost, allocation| {
+                let votes = allocation.votes as u128;
+                cost.checked_add(votes.checked_mul(votes)?)
+            });
+            match cost {
+                Some(cost) if cost <= *credits as u128 => {}
+                _ => {
+                    return Err(ContractError::InsufficientCredits {
+                        cost: cost.unwrap_or(u128::MAX),
+                        credits: *credits,
+                    })
+                }
+            }
+            allocations
+                .iter()
+                .map(|allocation| (&allocation.option, allocation.votes))
+                .collect()
+        }
+        (_, Choice::Multiple(_)) | (_, Choice::Allocations(_)) | (_, Choice::Sealed { .. }) => {
+            return Err(ContractError::InvalidChoice {})
+        }
     };
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{attr, from_binary}; // helper to construct an attribute e.g. ("action", "instantiate")
 
-    use super::execute; // mock functions to mock an environment, message info, dependencies // our instantate method
+    let mut positions: Vec<(usize, u64)> = vec![];
+    for (selection, votes) in selections {
+        let position = poll
+            .options
+            .iter()
+            .position(|option| option.text == *selection)
+            .ok_or(ContractError::Unauthorized {})?; // The option does not exist
+        if positions.iter().any(|(p, _)| *p == position) {
+            return Err(ContractError::DuplicateChoice {});
+        }
+        positions.push((position, votes));
+    }
+    Ok(positions)
+}
 
-    // Two fake addresses we will use to mock_info
-    pub const ADDR1: &str = "addr1";
-    pub const ADDR2: &str = "addr2";
+// What a ballot adds to each option, ranked ballots only count their first preference
+fn counted_votes(
+    poll: &Poll,
+    vote: &Choice,
+    weight: Uint128,
+) -> Result<Vec<(usize, Uint128)>, ContractError> {
+    let mut selections = selected_options(poll, vote)?;
+    if poll.kind == PollKind::Ranked {
+        selections.truncate(1);
+    }
+    Ok(selections
+        .into_iter()
+        .map(|(position, votes)| (position, weight * Uint128::from(votes)))
+        .collect())
+}
 
-    #[test]
-    fn test_instantiate() {
-        // Mock the dependencies, must be mutable so we can pass it as a mutable, empty vector means our contract has no balance
-        let mut deps = mock_dependencies();
-        // Mock the contract environment, contains the block info, contract address, etc.
-        let env = mock_env();
-        // Mock the message info, ADDR1 will be the sender, the empty vec means we sent no funds.
-        let info = mock_info(ADDR1, &[]);
+fn execute_reveal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    vote: Choice,
+    salt: String,
+) -> Result<Response, ContractError> {
+    let mut poll = polls()
+        .may_load(deps.storage, &poll_id)?
+        .ok_or(ContractError::PollNotExisted)?;
+    // Reveals carry on while paused or frozen, the reveal period would otherwise
+    // run out and take the sealed votes with it
+    if poll.status(&env.block) != PollStatus::Revealing {
+        return Err(ContractError::NotRevealPeriod {});
+    }
 
-        // Create a message where we (the sender) will be an admin
-        let msg = InstantiateMsg { admin: None };
-        // Call instantiate, unwrap to assert success
-        let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+    let mut ballot = BALLOTS
+        .may_load(deps.storage, (info.sender.clone(), &poll_id))?
+        .ok_or(ContractError::NothingToReveal {})?;
+    match &ballot.option {
+        Choice::Sealed { commitment }
+            if *commitment == commitment_hash(&poll_id, &info.sender, &vote, &salt)? => {}
+        Choice::Sealed { .. } => return Err(ContractError::InvalidReveal {}),
+        _ => return Err(ContractError::NothingToReveal {}),
+    }
 
-        assert_eq!(
-            res.attributes,
-            vec![attr("action", "instantiate"), attr("admin", ADDR1)]
-        )
+    // Now the vote is known it can be counted
+    tally(&mut poll, &vote, ballot.weight)?;
+
+    ballot.option = vote;
+    BALLOTS.save(deps.storage, (info.sender.clone(), &poll_id), &ballot)?;
+    POLL_BALLOTS.save(deps.storage, (&poll_id, info.sender.clone()), &ballot)?;
+    polls().save(deps.storage, &poll_id, &poll)?;
+
+    let event = Event::new("reveal")
+        .add_attribute("poll_id", &poll_id)
+        .add_attribute("voter", &info.sender)
+        .add_attribute("option", json_string(&ballot.option)?)
+        .add_attribute("weight", ballot.weight)
+        .add_attribute("tallies", tallies_json(&poll)?)
+        .add_attribute("turnout", poll.turnout);
+    Ok(Response::new()
+        .add_attribute("action", "reveal")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("voter", info.sender)
+        .add_event(event))
+}
+
+// What a sealed vote must commit to, the SHA-256 of the poll ID, the voter's address,
+// the vote's JSON and the salt one after the other. Tying it to the poll and voter
+// stops anyone copying a commitment and replaying its reveal
+fn commitment_hash(poll_id: &str, voter: &Addr, vote: &Choice, salt: &str) -> StdResult<Binary> {
+    let mut hasher = Sha256::new();
+    hasher.update(poll_id.as_bytes());
+    hasher.update(voter.as_bytes());
+    hasher.update(to_vec(vote)?);
+    hasher.update(salt.as_bytes());
+    Ok(Binary::from(hasher.finalize().to_vec()))
+}
+
+fn execute_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let poll = polls()
+        .may_load(deps.storage, &poll_id)?
+        .ok_or(ContractError::PollNotExisted)?;
+    if !matches!(
+        poll.status(&env.block),
+        PollStatus::Closed | PollStatus::Executed
+    ) {
+        return Err(ContractError::PollNotClosed {});
     }
 
-    #[test]
-    fn test_instantiate_with_admin() {
-        let mut deps = mock_dependencies();
-        let env = mock_env();
-        // Send as ADDR1 to show admin is different
-        let info = mock_info(ADDR1, &[]);
+    let mut ballot = BALLOTS
+        .may_load(deps.storage, (info.sender.clone(), &poll_id))?
+        .ok_or(ContractError::NothingToWithdraw {})?;
+    if ballot.withdrawn {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+    let refund = refund_msg(&poll, &info.sender, ballot.weight)?
+        .ok_or(ContractError::NothingToWithdraw {})?;
 
-        // Create a message where ADDR2 will be an admin
-        // Have to use .to_string() method
-        let msg = InstantiateMsg {
-            admin: Some(ADDR2.to_string()),
-        };
-        // Unwrap to assert success
-        let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-        // Assert admin is ADDR2 instead
-        assert_eq!(
-            res.attributes,
-            vec![attr("action", "instantiate"), attr("admin", ADDR2),]
-        );
+    // The ballot stays so the tally can still be audited
+    ballot.withdrawn = true;
+    BALLOTS.save(deps.storage, (info.sender.clone(), &poll_id), &ballot)?;
+    POLL_BALLOTS.save(deps.storage, (&poll_id, info.sender.clone()), &ballot)?;
+
+    Ok(Response::new()
+        .add_message(refund)
+        .add_attribute("action", "withdraw")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("voter", info.sender)
+        .add_attribute("amount", ballot.weight))
+}
+
+// The message sending locked tokens back, None if the poll does not lock any
+fn refund_msg(poll: &Poll, to: &Addr, amount: Uint128) -> StdResult<Option<CosmosMsg>> {
+    let msg: CosmosMsg = match &poll.voting_power {
+        VotingPower::Equal | VotingPower::Cw4Group { .. } => return Ok(None),
+        VotingPower::NativeToken { denom } => BankMsg::Send {
+            to_address: to.to_string(),
+            amount: coins(amount.u128(), denom),
+        }
+        .into(),
+        VotingPower::Cw20Token { address } => WasmMsg::Execute {
+            contract_addr: address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: to.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    };
+    Ok(Some(msg))
+}
+
+// Only the contract admin or the creator of the poll may manage it
+fn load_managed_poll(deps: Deps, info: &MessageInfo, poll_id: &str) -> Result<Poll, ContractError> {
+    let poll = polls()
+        .may_load(deps.storage, poll_id)?
+        .ok_or(ContractError::PollNotExisted)?;
+    let config = CONFIG.load(deps.storage)?;
+    if !config.is_admin(&info.sender) && info.sender != poll.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(poll)
+}
+
+fn execute_close_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let mut poll = load_managed_poll(deps.as_ref(), &info, &poll_id)?;
+    if matches!(
+        poll.status(&env.block),
+        PollStatus::Revealing | PollStatus::Closed | PollStatus::Executed
+    ) {
+        return Err(ContractError::PollClosed {});
+    }
+
+    // Freeze the poll by ending it at the current height
+    poll.end = Some(Expiration::AtHeight(env.block.height));
+    polls().save(deps.storage, &poll_id, &poll)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "close_poll")
+        .add_attribute("poll_id", poll_id))
+}
+
+fn execute_delete_poll(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let poll = load_managed_poll(deps.as_ref(), &info, &poll_id)?;
+
+    let ballots = POLL_BALLOTS
+        .prefix(&poll_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut msgs = vec![];
+    for (voter, ballot) in ballots {
+        // Hand back any tokens still locked by the ballot
+        if !ballot.withdrawn {
+            msgs.extend(refund_msg(&poll, &voter, ballot.weight)?);
+        }
+        BALLOTS.remove(deps.storage, (voter.clone(), &poll_id));
+        POLL_BALLOTS.remove(deps.storage, (&poll_id, voter));
+    }
+    // A deposit still held is forfeited to the admin, so deleting a poll cannot dodge FlagSpam.
+    // Without an admin nobody can flag spam, so it goes back to the creator
+    if let Some(deposit) = &poll.deposit {
+        let to = CONFIG.load(deps.storage)?.admin.unwrap_or(poll.creator);
+        msgs.push(
+            BankMsg::Send {
+                to_address: to.to_string(),
+                amount: vec![deposit.clone()],
+            }
+            .into(),
+        );
+    }
+    let allowed = ALLOWLIST
+        .prefix(&poll_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for voter in allowed {
+        ALLOWLIST.remove(deps.storage, (&poll_id, voter));
+    }
+    let delegations = POLL_DELEGATIONS
+        .prefix(&poll_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (delegator, delegate) in delegations {
+        POLL_DELEGATIONS.remove(deps.storage, (&poll_id, delegator.clone()));
+        POLL_DELEGATORS.remove(deps.storage, (&poll_id, delegate, delegator));
+    }
+    let delegated = DELEGATED_VOTES
+        .prefix(&poll_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (delegator, delegated) in delegated {
+        DELEGATED_VOTES.remove(deps.storage, (&poll_id, delegator.clone()));
+        DELEGATOR_VOTES.remove(deps.storage, (delegator.clone(), &poll_id));
+        DELEGATE_VOTES.remove(deps.storage, (&poll_id, delegated.delegate, delegator));
+    }
+    RANKED_TALLIES.remove(deps.storage, &poll_id);
+    polls().remove(deps.storage, &poll_id)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "delete_poll")
+        .add_attribute("poll_id", poll_id))
+}
+
+fn execute_execute(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let mut poll = polls()
+        .may_load(deps.storage, &poll_id)?
+        .ok_or(ContractError::PollNotExisted)?;
+    match poll.status(&env.block) {
+        PollStatus::Executed => return Err(ContractError::AlreadyExecuted {}),
+        PollStatus::Closed => {}
+        PollStatus::Pending | PollStatus::Open | PollStatus::Revealing => {
+            return Err(ContractError::PollNotClosed {})
+        }
+    }
+
+    let tallies = counted_tallies(deps.as_ref(), &env, &poll_id, &poll)?;
+    let result = poll_result(deps.as_ref(), &env, &poll_id, &poll, tallies)?;
+    let winner = match (result.outcome, result.winner) {
+        (PollOutcome::Passed, Some(winner)) => winner,
+        _ => return Err(ContractError::PollNotPassed {}),
+    };
+    let msgs = poll
+        .options
+        .iter()
+        .find(|option| option.text == winner)
+        .map(|option| option.msgs.clone())
+        .unwrap_or_default();
+
+    // Marked before dispatching so the messages can only ever run once
+    poll.executed = true;
+    polls().save(deps.storage, &poll_id, &poll)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "execute")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("option", winner))
+}
+
+fn execute_tally_ranked(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    poll_id: String,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let poll = polls()
+        .may_load(deps.storage, &poll_id)?
+        .ok_or(ContractError::PollNotExisted)?;
+    if poll.kind != PollKind::Ranked {
+        return Err(ContractError::NotRanked {});
+    }
+    // Ballots only stop changing once the poll has closed
+    if poll.status(&env.block) != PollStatus::Closed {
+        return Err(ContractError::PollNotClosed {});
+    }
+    let mut tally = match RANKED_TALLIES.may_load(deps.storage, &poll_id)? {
+        Some(tally) if tally.finished => return Err(ContractError::AlreadyTallied {}),
+        Some(tally) => tally,
+        None => RankedTally {
+            tallies: poll
+                .options
+                .iter()
+                .map(|option| PollOption::new(option.text.clone()))
+                .collect(),
+            counted_to: None,
+            finished: false,
+        },
+    };
+
+    let limit = limit.unwrap_or(DEFAULT_TALLY_LIMIT).min(MAX_TALLY_LIMIT) as usize;
+    let start = tally.counted_to.clone().map(Bound::exclusive);
+    let ballots = POLL_BALLOTS
+        .prefix(&poll_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (_, ballot) in &ballots {
+        count_preference(&mut tally.tallies, &ranking(&ballot.option), ballot.weight);
+    }
+    if let Some((voter, _)) = ballots.last() {
+        tally.counted_to = Some(voter.clone());
+    }
+    // A short batch means the round has every ballot, so it can be settled
+    if ballots.len() < limit {
+        match round_loser(&tally.tallies) {
+            Some(loser) => {
+                tally.tallies = tally
+                    .tallies
+                    .iter()
+                    .filter(|option| option.text != loser)
+                    .map(|option| PollOption::new(option.text.clone()))
+                    .collect();
+                tally.counted_to = None;
+            }
+            None => tally.finished = true,
+        }
+    }
+    RANKED_TALLIES.save(deps.storage, &poll_id, &tally)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "tally_ranked")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("counted", ballots.len().to_string())
+        .add_attribute("finished", tally.finished.to_string()))
+}
+
+fn execute_claim_deposit(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let mut poll = polls()
+        .may_load(deps.storage, &poll_id)?
+        .ok_or(ContractError::PollNotExisted)?;
+    let deposit = poll.deposit.take().ok_or(ContractError::NoDeposit {})?;
+
+    // Only polls that drew enough voters get their deposit back, and without a
+    // quorum that still means somebody voted
+    let tallies = counted_tallies(deps.as_ref(), &env, &poll_id, &poll)?;
+    match poll_result(deps.as_ref(), &env, &poll_id, &poll, tallies)?.outcome {
+        PollOutcome::Pending => return Err(ContractError::PollNotClosed {}),
+        PollOutcome::NoQuorum => return Err(ContractError::QuorumNotReached {}),
+        PollOutcome::Passed | PollOutcome::Rejected => {}
+    }
+    if poll.turnout.is_zero() {
+        return Err(ContractError::QuorumNotReached {});
+    }
+    polls().save(deps.storage, &poll_id, &poll)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: poll.creator.to_string(),
+            amount: vec![deposit.clone()],
+        })
+        .add_attribute("action", "claim_deposit")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("amount", deposit.to_string()))
+}
+
+fn execute_flag_spam(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let admin = match config.admin {
+        Some(admin) if admin == info.sender => admin,
+        _ => return Err(ContractError::Unauthorized {}),
+    };
+    let mut poll = polls()
+        .may_load(deps.storage, &poll_id)?
+        .ok_or(ContractError::PollNotExisted)?;
+
+    // The creator forfeits the deposit to the admin
+    let deposit = poll.deposit.take().ok_or(ContractError::NoDeposit {})?;
+    polls().save(deps.storage, &poll_id, &poll)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: admin.to_string(),
+            amount: vec![deposit.clone()],
+        })
+        .add_attribute("action", "flag_spam")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("amount", deposit.to_string()))
+}
+
+// Only the creator may change who can vote on their poll
+fn load_allowlisted_poll(
+    deps: Deps,
+    info: &MessageInfo,
+    poll_id: &str,
+) -> Result<Poll, ContractError> {
+    let poll = polls()
+        .may_load(deps.storage, poll_id)?
+        .ok_or(ContractError::PollNotExisted)?;
+    if info.sender != poll.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !poll.allowlisted {
+        return Err(ContractError::PollNotAllowlisted {});
+    }
+    Ok(poll)
+}
+
+fn execute_add_voters(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    voters: Vec<String>,
+) -> Result<Response, ContractError> {
+    load_allowlisted_poll(deps.as_ref(), &info, &poll_id)?;
+
+    for voter in &voters {
+        let voter = deps.api.addr_validate(voter)?;
+        ALLOWLIST.save(deps.storage, (&poll_id, voter), &())?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "add_voters")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("count", voters.len().to_string()))
+}
+
+fn execute_remove_voters(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    voters: Vec<String>,
+) -> Result<Response, ContractError> {
+    load_allowlisted_poll(deps.as_ref(), &info, &poll_id)?;
+
+    // Ballots already cast stay counted, the voter just cannot vote again
+    for voter in &voters {
+        let voter = deps.api.addr_validate(voter)?;
+        ALLOWLIST.remove(deps.storage, (&poll_id, voter));
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_voters")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("count", voters.len().to_string()))
+}
+
+fn execute_delegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    to: String,
+    poll_id: Option<String>,
+) -> Result<Response, ContractError> {
+    let delegate = deps.api.addr_validate(&to)?;
+    if delegate == info.sender {
+        return Err(ContractError::SelfDelegation {});
+    }
+
+    // Replace any earlier delegation in the same scope
+    let mut events = vec![];
+    match &poll_id {
+        Some(poll_id) => {
+            if !polls().has(deps.storage, poll_id) {
+                return Err(ContractError::PollNotExisted);
+            }
+            let key = (poll_id.as_str(), info.sender.clone());
+            let old = POLL_DELEGATIONS.may_load(deps.storage, key.clone())?;
+            if old.as_ref() != Some(&delegate) {
+                let delegators = POLL_DELEGATORS
+                    .prefix((poll_id, delegate.clone()))
+                    .keys(deps.storage, None, None, Order::Ascending)
+                    .take(MAX_DELEGATORS)
+                    .count();
+                if delegators >= MAX_DELEGATORS {
+                    return Err(ContractError::TooManyDelegators {});
+                }
+            }
+            // Whoever was casting their weight on this poll, directly or through a
+            // global delegation, stops doing so
+            let casting = match &old {
+                Some(old) => Some(old.clone()),
+                None => DELEGATIONS.may_load(deps.storage, info.sender.clone())?,
+            };
+            if let Some(casting) = casting.filter(|casting| *casting != delegate) {
+                events.extend(release_delegated_vote(
+                    deps.storage,
+                    &env,
+                    poll_id,
+                    &info.sender,
+                    &casting,
+                )?);
+            }
+            if let Some(old) = old {
+                POLL_DELEGATORS.remove(deps.storage, (poll_id, old, info.sender.clone()));
+            }
+            POLL_DELEGATIONS.save(deps.storage, key, &delegate)?;
+            POLL_DELEGATORS.save(
+                deps.storage,
+                (poll_id, delegate.clone(), info.sender.clone()),
+                &(),
+            )?;
+        }
+        None => {
+            let old = DELEGATIONS.may_load(deps.storage, info.sender.clone())?;
+            if old.as_ref() != Some(&delegate) {
+                let delegators = DELEGATORS
+                    .prefix(delegate.clone())
+                    .keys(deps.storage, None, None, Order::Ascending)
+                    .take(MAX_DELEGATORS)
+                    .count();
+                if delegators >= MAX_DELEGATORS {
+                    return Err(ContractError::TooManyDelegators {});
+                }
+            }
+            if let Some(old) = old {
+                if old != delegate {
+                    events = release_delegated_votes(deps.storage, &env, &info.sender, &old)?;
+                }
+                DELEGATORS.remove(deps.storage, (old, info.sender.clone()));
+            }
+            DELEGATIONS.save(deps.storage, info.sender.clone(), &delegate)?;
+            DELEGATORS.save(deps.storage, (delegate.clone(), info.sender.clone()), &())?;
+        }
+    }
+
+    Ok(Response::new()
+        .add_events(events)
+        .add_attribute("action", "delegate")
+        .add_attribute("delegator", info.sender)
+        .add_attribute("delegate", delegate)
+        .add_attribute("poll_id", poll_id.unwrap_or_default()))
+}
+
+fn execute_undelegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: Option<String>,
+) -> Result<Response, ContractError> {
+    let events = match &poll_id {
+        Some(poll_id) => {
+            let key = (poll_id.as_str(), info.sender.clone());
+            let delegate = POLL_DELEGATIONS
+                .may_load(deps.storage, key.clone())?
+                .ok_or(ContractError::NotDelegated {})?;
+            let event =
+                release_delegated_vote(deps.storage, &env, poll_id, &info.sender, &delegate)?;
+            POLL_DELEGATIONS.remove(deps.storage, key);
+            POLL_DELEGATORS.remove(deps.storage, (poll_id, delegate, info.sender.clone()));
+            event.into_iter().collect()
+        }
+        None => {
+            let delegate = DELEGATIONS
+                .may_load(deps.storage, info.sender.clone())?
+                .ok_or(ContractError::NotDelegated {})?;
+            let events = release_delegated_votes(deps.storage, &env, &info.sender, &delegate)?;
+            DELEGATIONS.remove(deps.storage, info.sender.clone());
+            DELEGATORS.remove(deps.storage, (delegate, info.sender.clone()));
+            events
+        }
+    };
+
+    Ok(Response::new()
+        .add_events(events)
+        .add_attribute("action", "undelegate")
+        .add_attribute("delegator", info.sender)
+        .add_attribute("poll_id", poll_id.unwrap_or_default()))
+}
+
+fn execute_update_limits(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    limits: PollLimits,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if !config.is_admin(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    validation::validate_limits(&limits)?;
+
+    // Only new polls are held to the new limits
+    config.limits = limits;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "update_limits"))
+}
+
+fn execute_update_deposit(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    deposit: Option<Coin>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if !config.is_admin(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Existing polls keep the deposit they were created with
+    config.deposit = deposit.filter(|deposit| !deposit.amount.is_zero());
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "update_deposit"))
+}
+
+fn execute_propose_new_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_admin: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !config.is_admin(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Nothing changes until the new admin accepts, proposing again replaces the offer
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    PENDING_ADMIN.save(deps.storage, &new_admin)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_admin")
+        .add_attribute("new_admin", new_admin))
+}
+
+fn execute_accept_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if PENDING_ADMIN.may_load(deps.storage)?.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    PENDING_ADMIN.remove(deps.storage);
+    let mut config = CONFIG.load(deps.storage)?;
+    config.admin = Some(info.sender.clone());
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_admin")
+        .add_attribute("admin", info.sender))
+}
+
+fn execute_renounce_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if !config.is_admin(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // For good, nobody can take over after this, so nobody could lift a pause either
+    PENDING_ADMIN.remove(deps.storage);
+    config.admin = None;
+    config.paused = false;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "renounce_admin"))
+}
+
+fn execute_set_paused(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if !config.is_admin(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.paused = paused;
+    CONFIG.save(deps.storage, &config)?;
+
+    let action = if paused { "pause" } else { "unpause" };
+    Ok(Response::new().add_attribute("action", action))
+}
+
+fn execute_set_frozen(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    frozen: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !config.is_admin(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut poll = polls()
+        .may_load(deps.storage, &poll_id)?
+        .ok_or(ContractError::PollNotExisted)?;
+
+    poll.frozen = frozen;
+    polls().save(deps.storage, &poll_id, &poll)?;
+
+    let action = if frozen {
+        "freeze_poll"
+    } else {
+        "unfreeze_poll"
+    };
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("poll_id", poll_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidContract {
+            contract: stored.contract,
+        });
+    }
+
+    let stored_version: semver::Version = stored.version.parse()?;
+    let current_version: semver::Version = CONTRACT_VERSION.parse()?;
+    if stored_version > current_version {
+        return Err(ContractError::CannotDowngrade {
+            stored: stored.version,
+            current: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    // Run every state migration the stored version has not seen yet, oldest first
+    if stored_version < semver::Version::new(0, 2, 0) {
+        migrations::v0_2::migrate_poll_options(deps.storage)?;
+    }
+    if stored_version < semver::Version::new(0, 3, 0) {
+        migrations::v0_3::index_polls_by_creator(deps.storage)?;
+    }
+    if stored_version < semver::Version::new(0, 4, 0) {
+        migrations::v0_4::index_ballots_by_poll(deps.storage)?;
+    }
+    if stored_version < semver::Version::new(0, 5, 0) {
+        migrations::v0_5::count_turnout(deps.storage)?;
+    }
+    if stored_version < semver::Version::new(0, 6, 0) {
+        migrations::v0_6::seed_poll_count(deps.storage)?;
+    }
+    if stored_version < semver::Version::new(0, 7, 0) {
+        migrations::v0_7::count_ballots(deps.storage)?;
+    }
+    if stored_version < semver::Version::new(0, 8, 0) {
+        migrations::v0_8::index_delegated_votes(deps.storage)?;
+    }
+    if stored_version < semver::Version::new(0, 9, 0) {
+        migrations::v0_9::index_votes_by_delegate(deps.storage)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => query_config(deps, env),
+        QueryMsg::AllPolls {
+            start_after,
+            limit,
+            order,
+            creator,
+            status,
+        } => query_all_polls(deps, env, start_after, limit, order, creator, status),
+        QueryMsg::PollsByCreator {
+            creator,
+            start_after,
+            limit,
+        } => query_polls_by_creator(deps, env, creator, start_after, limit),
+        QueryMsg::Poll { poll_id } => query_poll(deps, env, poll_id),
+        QueryMsg::Vote { address, poll_id } => query_vote(deps, env, address, poll_id),
+        QueryMsg::VotesByVoter {
+            address,
+            start_after,
+            limit,
+        } => query_votes_by_voter(deps, env, address, start_after, limit),
+        QueryMsg::PollVoters {
+            poll_id,
+            start_after,
+            limit,
+        } => query_poll_voters(deps, env, poll_id, start_after, limit),
+        QueryMsg::RankedResult { poll_id } => query_ranked_result(deps, env, poll_id),
+        QueryMsg::PollResult { poll_id } => query_poll_result(deps, env, poll_id),
+        QueryMsg::UnrevealedVotes {
+            poll_id,
+            start_after,
+            limit,
+        } => query_unrevealed_votes(deps, env, poll_id, start_after, limit),
+        QueryMsg::IsEligible { poll_id, address } => query_is_eligible(deps, env, poll_id, address),
+        QueryMsg::Delegation { address, poll_id } => query_delegation(deps, env, address, poll_id),
+    }
+}
+
+fn query_config(deps: Deps, _env: Env) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    to_binary(&ConfigResponse {
+        admin: config.admin,
+        pending_admin: PENDING_ADMIN.may_load(deps.storage)?,
+        limits: config.limits,
+        deposit: config.deposit,
+        paused: config.paused,
+    })
+}
+
+fn query_all_polls(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order: Option<OrderBy>,
+    creator: Option<String>,
+    status: Option<PollStatus>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let order: Order = order.unwrap_or(OrderBy::Ascending).into();
+    let creator = creator
+        .map(|creator| deps.api.addr_validate(&creator))
+        .transpose()?;
+
+    // start_after is the lower bound when ascending and the upper bound when descending
+    let (min, max) = match order {
+        Order::Ascending => (start_after, None),
+        Order::Descending => (None, start_after),
+    };
+
+    // With a creator we only walk that creator's slice of the index
+    let range: Box<dyn Iterator<Item = StdResult<(String, Poll)>>> = match creator {
+        Some(creator) => polls().idx.creator.prefix(creator).range(
+            deps.storage,
+            min.map(Bound::exclusive),
+            max.map(Bound::exclusive),
+            order,
+        ),
+        None => polls().range(
+            deps.storage,
+            min.as_deref().map(Bound::exclusive),
+            max.as_deref().map(Bound::exclusive),
+            order,
+        ),
+    };
+
+    let mut polls = vec![];
+    let mut scanned = 0;
+    let mut last_scanned = None;
+    for item in range.take(MAX_SCAN) {
+        let (poll_id, poll) = item?;
+        scanned += 1;
+        last_scanned = Some(poll_id.clone());
+        let poll_status = poll.status(&env.block);
+        if status.map_or(true, |s| s == poll_status) {
+            polls.push(PollInfo {
+                poll_id,
+                poll,
+                status: poll_status,
+            });
+            if polls.len() == limit {
+                break;
+            }
+        }
+    }
+    // Tell the caller where to carry on when the scan ran out before the page filled up
+    let last_scanned = last_scanned.filter(|_| polls.len() < limit && scanned == MAX_SCAN);
+
+    to_binary(&AllPollsResponse {
+        polls,
+        last_scanned,
+    })
+}
+
+fn query_polls_by_creator(
+    deps: Deps,
+    env: Env,
+    creator: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    query_all_polls(deps, env, start_after, limit, None, Some(creator), None)
+}
+
+fn query_poll(deps: Deps, env: Env, poll_id: String) -> StdResult<Binary> {
+    let poll = polls().may_load(deps.storage, &poll_id)?;
+    let status = poll.as_ref().map(|poll| poll.status(&env.block));
+    to_binary(&PollResponse { poll, status })
+}
+
+fn query_vote(deps: Deps, _env: Env, address: String, poll_id: String) -> StdResult<Binary> {
+    let validated_address = deps.api.addr_validate(&address).unwrap();
+    let vote = BALLOTS.may_load(deps.storage, (validated_address, &poll_id))?;
+
+    to_binary(&VoteResponse { vote })
+}
+
+fn query_votes_by_voter(
+    deps: Deps,
+    _env: Env,
+    address: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let voter = deps.api.addr_validate(&address)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let min = start_after.as_deref().map(Bound::exclusive);
+
+    // BALLOTS is keyed by voter first, so the voter's ballots are one prefix
+    let votes = BALLOTS
+        .prefix(voter.clone())
+        .range(deps.storage, min, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (poll_id, ballot) = item?;
+            Ok(BallotInfo {
+                poll_id,
+                voter: voter.clone(),
+                ballot,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&VotesResponse { votes })
+}
+
+fn query_poll_voters(
+    deps: Deps,
+    _env: Env,
+    poll_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let min = start_after.map(Bound::exclusive);
+
+    let votes = POLL_BALLOTS
+        .prefix(&poll_id)
+        .range(deps.storage, min, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (voter, ballot) = item?;
+            Ok(BallotInfo {
+                poll_id: poll_id.clone(),
+                voter,
+                ballot,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&VotesResponse { votes })
+}
+
+fn query_unrevealed_votes(
+    deps: Deps,
+    _env: Env,
+    poll_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let min = start_after.map(Bound::exclusive);
+
+    let votes = POLL_BALLOTS
+        .prefix(&poll_id)
+        .range(deps.storage, min, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref().map_or(true, |(_, ballot)| {
+                matches!(ballot.option, Choice::Sealed { .. })
+            })
+        })
+        .take(limit)
+        .map(|item| {
+            let (voter, ballot) = item?;
+            Ok(BallotInfo {
+                poll_id: poll_id.clone(),
+                voter,
+                ballot,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&VotesResponse { votes })
+}
+
+fn query_ranked_result(deps: Deps, _env: Env, poll_id: String) -> StdResult<Binary> {
+    let poll = polls().load(deps.storage, &poll_id)?;
+    if poll.kind != PollKind::Ranked {
+        return Err(StdError::generic_err("Poll is not ranked choice"));
+    }
+
+    to_binary(&instant_runoff(deps, &poll_id, &poll)?)
+}
+
+// Instant-runoff over every ranked ballot in the poll, one round per elimination
+fn instant_runoff(deps: Deps, poll_id: &str, poll: &Poll) -> StdResult<RankedResultResponse> {
+    let rankings = POLL_BALLOTS
+        .prefix(poll_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (_, ballot) = item?;
+            Ok((ranking(&ballot.option), ballot.weight))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut active: Vec<String> = poll.options.iter().map(|o| o.text.clone()).collect();
+    let mut rounds = vec![];
+    let winner = loop {
+        let mut tallies: Vec<PollOption> = active.iter().cloned().map(PollOption::new).collect();
+        for (ranking, weight) in &rankings {
+            count_preference(&mut tallies, ranking, *weight);
+        }
+
+        match round_loser(&tallies) {
+            Some(loser) => {
+                active.retain(|option| *option != loser);
+                rounds.push(RankedRound {
+                    tallies,
+                    eliminated: Some(loser),
+                });
+            }
+            None => {
+                let winner = majority(&tallies);
+                rounds.push(RankedRound {
+                    tallies,
+                    eliminated: None,
+                });
+                break winner;
+            }
+        }
+    };
+
+    Ok(RankedResultResponse { rounds, winner })
+}
+
+// A ballot's options in order of preference
+fn ranking(choice: &Choice) -> Vec<String> {
+    match choice {
+        Choice::Single(option) => vec![option.clone()],
+        Choice::Multiple(options) => options.clone(),
+        Choice::Allocations(_) => vec![], // never accepted by a ranked poll
+        Choice::Sealed { .. } => vec![],  // not revealed, so not counted
+    }
+}
+
+// Each ballot counts for its highest ranked option still in the running
+fn count_preference(tallies: &mut [PollOption], ranking: &[String], weight: Uint128) {
+    let preference = ranking
+        .iter()
+        .find_map(|option| tallies.iter().position(|tally| tally.text == *option));
+    if let Some(index) = preference {
+        tallies[index].votes += weight;
+    }
+}
+
+// The option holding a majority of the ballots still in play
+fn majority(tallies: &[PollOption]) -> Option<String> {
+    let total = tallies
+        .iter()
+        .fold(Uint128::zero(), |total, tally| total + tally.votes);
+    tallies
+        .iter()
+        .find(|tally| tally.votes + tally.votes > total)
+        .map(|tally| tally.text.clone())
+}
+
+// The option a fully counted round knocks out, None once there is a majority or
+// nothing left to count. Ties go against the one listed last
+fn round_loser(tallies: &[PollOption]) -> Option<String> {
+    if majority(tallies).is_some() || tallies.iter().all(|tally| tally.votes.is_zero()) {
+        return None;
+    }
+    tallies
+        .iter()
+        .rev()
+        .min_by_key(|tally| tally.votes)
+        .map(|tally| tally.text.clone())
+}
+
+fn query_poll_result(deps: Deps, env: Env, poll_id: String) -> StdResult<Binary> {
+    let poll = polls().load(deps.storage, &poll_id)?;
+    // Ranked polls are decided on the last instant-runoff round, the rest on the raw counts.
+    // Until TallyRanked has finished the query runs the count itself
+    let tallies = if poll.kind == PollKind::Ranked {
+        match RANKED_TALLIES.may_load(deps.storage, &poll_id)? {
+            Some(tally) if tally.finished => tally.tallies,
+            _ => instant_runoff(deps, &poll_id, &poll)?
+                .rounds
+                .pop()
+                .map(|round| round.tallies)
+                .unwrap_or_default(),
+        }
+    } else {
+        poll.options.clone()
+    };
+    to_binary(&poll_result(deps, &env, &poll_id, &poll, tallies)?)
+}
+
+// The tallies Execute and ClaimDeposit decide a poll on, which for a ranked poll
+// is the last round of its finished TallyRanked count
+fn counted_tallies(
+    deps: Deps,
+    env: &Env,
+    poll_id: &str,
+    poll: &Poll,
+) -> Result<Vec<PollOption>, ContractError> {
+    if poll.kind != PollKind::Ranked {
+        return Ok(poll.options.clone());
+    }
+    match RANKED_TALLIES.may_load(deps.storage, poll_id)? {
+        Some(tally) if tally.finished => Ok(tally.tallies),
+        _ if poll.status(&env.block) == PollStatus::Closed => Err(ContractError::NotTallied {}),
+        _ => Err(ContractError::PollNotClosed {}),
+    }
+}
+
+// Decide the poll on the given tallies against its quorum and threshold
+fn poll_result(
+    deps: Deps,
+    env: &Env,
+    poll_id: &str,
+    poll: &Poll,
+    tallies: Vec<PollOption>,
+) -> StdResult<PollResultResponse> {
+    let total_votes = tallies
+        .iter()
+        .fold(Uint128::zero(), |total, tally| total + tally.votes);
+    let mut ranked: Vec<&PollOption> = tallies.iter().collect();
+    ranked.sort_by_key(|o| std::cmp::Reverse(o.votes));
+    let winning_votes = ranked.first().map(|o| o.votes).unwrap_or_default();
+    let runner_up = ranked.get(1).map(|o| o.votes).unwrap_or_default();
+    // A tie at the top has no winner
+    let winner = ranked
+        .first()
+        .filter(|o| !o.votes.is_zero() && o.votes > runner_up)
+        .map(|o| o.text.clone());
+
+    let eligible_weight = eligible_weight(deps, poll_id, poll)?;
+    let quorum_met = match &poll.quorum {
+        None => true,
+        Some(Requirement::Absolute(min)) => poll.turnout >= *min,
+        Some(Requirement::Percentage(pct)) => match eligible_weight {
+            Some(eligible) if !eligible.is_zero() => {
+                Decimal::from_ratio(poll.turnout, eligible) >= *pct
+            }
+            _ => false,
+        },
+    };
+    // Support is a share of the voting power that turned out, as approval ballots count
+    // towards several options. A quadratic ballot puts at most the square root of its
+    // credits on one option, so its share is taken of that
+    let support_base = match &poll.kind {
+        PollKind::Quadratic { credits } => poll.turnout * Uint128::from(integer_sqrt(*credits)),
+        _ => poll.turnout,
+    };
+    let threshold_met = match &poll.threshold {
+        None => true,
+        Some(Requirement::Absolute(min)) => winning_votes >= *min,
+        Some(Requirement::Percentage(pct)) => {
+            !support_base.is_zero() && Decimal::from_ratio(winning_votes, support_base) >= *pct
+        }
+    };
+
+    let outcome = if matches!(
+        poll.status(&env.block),
+        PollStatus::Pending | PollStatus::Open | PollStatus::Revealing
+    ) {
+        PollOutcome::Pending
+    } else if !quorum_met {
+        PollOutcome::NoQuorum
+    } else if winner.is_some() && threshold_met {
+        PollOutcome::Passed
+    } else {
+        PollOutcome::Rejected
+    };
+
+    Ok(PollResultResponse {
+        outcome,
+        winner,
+        winning_votes,
+        margin: winning_votes - runner_up,
+        total_votes,
+        turnout: poll.turnout,
+        eligible_weight,
+    })
+}
+
+// The largest number whose square is at most the value
+fn integer_sqrt(value: u64) -> u64 {
+    let value = value as u128;
+    let mut root = value;
+    let mut next = (root + 1) / 2;
+    while next < root {
+        root = next;
+        next = (root + value / root) / 2;
+    }
+    root as u64
+}
+
+// The total voting power that could take part, None when it cannot be known
+fn eligible_weight(deps: Deps, poll_id: &str, poll: &Poll) -> StdResult<Option<Uint128>> {
+    let weight = match &poll.voting_power {
+        VotingPower::Cw4Group { address } => match poll.group_weight {
+            Some(total) => Some(total),
+            // Polls from before the total was recorded only have the group's current one
+            None => {
+                let total = Cw4Contract::new(address.clone()).total_weight(&deps.querier)?;
+                Some(Uint128::from(total))
+            }
+        },
+        VotingPower::Cw20Token { address } => {
+            let info: TokenInfoResponse = deps
+                .querier
+                .query_wasm_smart(address, &Cw20QueryMsg::TokenInfo {})?;
+            Some(info.total_supply)
+        }
+        VotingPower::Equal if poll.allowlisted => {
+            let voters = ALLOWLIST
+                .prefix(poll_id)
+                .keys(deps.storage, None, None, Order::Ascending)
+                .count();
+            Some(Uint128::from(voters as u128))
+        }
+        VotingPower::Equal | VotingPower::NativeToken { .. } => None,
+    };
+    Ok(weight)
+}
+
+fn query_is_eligible(deps: Deps, _env: Env, poll_id: String, address: String) -> StdResult<Binary> {
+    let poll = polls().load(deps.storage, &poll_id)?;
+    let voter = deps.api.addr_validate(&address)?;
+    let mut eligible = !poll.allowlisted || ALLOWLIST.has(deps.storage, (&poll_id, voter.clone()));
+    // Group polls also need membership, with weight, as of the poll's creation
+    if let VotingPower::Cw4Group { address } = &poll.voting_power {
+        eligible = eligible
+            && Cw4Contract::new(address.clone())
+                .member_at_height(&deps.querier, voter, Some(poll.created_height))?
+                .map_or(false, |weight| weight > 0);
+    }
+    to_binary(&EligibleResponse { eligible })
+}
+
+fn query_delegation(
+    deps: Deps,
+    _env: Env,
+    address: String,
+    poll_id: Option<String>,
+) -> StdResult<Binary> {
+    let delegator = deps.api.addr_validate(&address)?;
+    // A delegation for the poll takes precedence over a global one
+    let for_poll = match &poll_id {
+        Some(poll_id) => POLL_DELEGATIONS.may_load(deps.storage, (poll_id, delegator.clone()))?,
+        None => None,
+    };
+    let delegate = match for_poll {
+        Some(delegate) => Some(delegate),
+        None => DELEGATIONS.may_load(deps.storage, delegator)?,
+    };
+    to_binary(&DelegationResponse { delegate })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contract::{
+        instantiate, migrate, query, CONTRACT_NAME, CONTRACT_VERSION, MAX_DELEGATORS, MAX_SCAN,
+    }; // the contract entry points
+    use crate::migrations::v0_2::{LegacyPoll, LEGACY_POLLS};
+    use crate::msg::{
+        AllPollsResponse, ConfigResponse, CreatePollResponse, DelegationResponse, EligibleResponse,
+        ExecuteMsg, InstantiateMsg, MigrateMsg, OrderBy, PollOutcome, PollResponse,
+        PollResultResponse, QueryMsg, RankedResultResponse, ReceiveMsg, VoteResponse,
+        VotesResponse,
+    };
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{
+        attr, coin, coins, from_binary, from_slice, to_binary, Addr, BankMsg, Binary,
+        ContractResult, CosmosMsg, Decimal, Env, Event, Order, StdError, StdResult, SubMsg,
+        SystemError, SystemResult, Uint128, WasmMsg, WasmQuery,
+    }; // helper to construct an attribute e.g. ("action", "instantiate")
+    use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+    use cw4::{Cw4QueryMsg, MemberResponse};
+    use cw_utils::{Expiration, Scheduled};
+    use sha2::{Digest, Sha256};
+
+    use crate::state::{
+        polls, sequence_poll_id, Allocation, Ballot, Choice, PollKind, PollLimits, PollOption,
+        PollStatus, Requirement, VotingPower, BALLOTS, DELEGATE_VOTES, POLL_BALLOTS, POLL_COUNT,
+        RANKED_TALLIES,
+    };
+    use crate::ContractError;
+
+    use super::execute; // mock functions to mock an environment, message info, dependencies // our instantate method
+
+    // Fake addresses we will use to mock_info
+    pub const ADDR1: &str = "addr1";
+    pub const ADDR2: &str = "addr2";
+    pub const ADDR3: &str = "addr3";
+    pub const ADDR4: &str = "addr4";
+    pub const TOKEN: &str = "token";
+    pub const GROUP: &str = "group";
+
+    #[test]
+    fn test_instantiate() {
+        // Mock the dependencies, must be mutable so we can pass it as a mutable, empty vector means our contract has no balance
+        let mut deps = mock_dependencies();
+        // Mock the contract environment, contains the block info, contract address, etc.
+        let env = mock_env();
+        // Mock the message info, ADDR1 will be the sender, the empty vec means we sent no funds.
+        let info = mock_info(ADDR1, &[]);
+
+        // Create a message where we (the sender) will be an admin
+        let msg = InstantiateMsg { admin: None };
+        // Call instantiate, unwrap to assert success
+        let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "instantiate"), attr("admin", ADDR1)]
+        )
+    }
+
+    #[test]
+    fn test_instantiate_with_admin() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        // Send as ADDR1 to show admin is different
+        let info = mock_info(ADDR1, &[]);
+
+        // Create a message where ADDR2 will be an admin
+        // Have to use .to_string() method
+        let msg = InstantiateMsg {
+            admin: Some(ADDR2.to_string()),
+        };
+        // Unwrap to assert success
+        let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+        // Assert admin is ADDR2 instead
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "instantiate"), attr("admin", ADDR2),]
+        );
+    }
+
+    #[test]
+    fn test_execute_admin_handover() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let config = |deps: &cosmwasm_std::OwnedDeps<_, _, _>| {
+            let bin = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+            from_binary::<ConfigResponse>(&bin).unwrap()
+        };
+
+        // Only the admin can propose a successor
+        let propose = ExecuteMsg::ProposeNewAdmin {
+            new_admin: ADDR2.to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            propose.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), propose).unwrap();
+        let res = config(&deps);
+        assert_eq!(res.admin, Some(Addr::unchecked(ADDR1)));
+        assert_eq!(res.pending_admin, Some(Addr::unchecked(ADDR2)));
+
+        // And only the proposed address can accept
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR3, &[]),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap();
+        let res = config(&deps);
+        assert_eq!(res.admin, Some(Addr::unchecked(ADDR2)));
+        assert_eq!(res.pending_admin, None);
+
+        // The old admin has lost their rights
+        let update = ExecuteMsg::UpdateDeposit { deposit: None };
+        let err = execute(deps.as_mut(), env.clone(), info, update.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // Once renounced nobody is admin
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            ExecuteMsg::RenounceAdmin {},
+        )
+        .unwrap();
+        assert_eq!(config(&deps).admin, None);
+        let err = execute(deps.as_mut(), env, mock_info(ADDR2, &[]), update).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn test_execute_pause() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create = |poll_id: &str| ExecuteMsg::CreatePoll {
+            poll_id: Some(poll_id.to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create("some_id")).unwrap();
+        let vote = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Juno".to_string()),
+        };
+
+        // Only the admin can pause
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+
+        // Nothing can be created or voted on, but queries still answer
+        let err =
+            execute(deps.as_mut(), env.clone(), info.clone(), create("other_id")).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), vote.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+        let msg = QueryMsg::Poll {
+            poll_id: "some_id".to_string(),
+        };
+        let _bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Unpause {},
+        )
+        .unwrap();
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), vote.clone()).unwrap();
+
+        // A frozen poll refuses votes on its own
+        let msg = ExecuteMsg::FreezePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), vote.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::PollFrozen {}));
+        let msg = ExecuteMsg::UnfreezePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), vote.clone()).unwrap();
+
+        // Renouncing the admin lifts the pause and any freeze, nobody could lift them after
+        let msg = ExecuteMsg::FreezePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::RenounceAdmin {},
+        )
+        .unwrap();
+        let bin = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+        let res: ConfigResponse = from_binary(&bin).unwrap();
+        assert!(!res.paused);
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create("other_id")).unwrap();
+        let _res = execute(deps.as_mut(), env, info, vote).unwrap();
+    }
+
+    #[test]
+    fn test_execute_create_poll_valid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // New execute msg
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec![
+                "Cosmos Hub".to_string(),
+                "Juno".to_string(),
+                "Osmosis".to_string(),
+            ],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+
+        // Unwrap to assert success
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "create_poll"),
+                attr("poll_id", "some_id"),
+                attr("creator", ADDR1)
+            ]
+        );
+        // The event carries the whole poll for indexers
+        assert_eq!(
+            res.events,
+            vec![Event::new("create_poll").add_attributes(vec![
+                ("poll_id", "some_id"),
+                ("creator", ADDR1),
+                ("question", "What's your favourite Cosmos coin?"),
+                ("option", "Cosmos Hub"),
+                ("msgs", "[]"),
+                ("option", "Juno"),
+                ("msgs", "[]"),
+                ("option", "Osmosis"),
+                ("msgs", "[]"),
+                ("start", "null"),
+                ("end", "null"),
+                ("voting_power", r#""equal""#),
+                ("kind", r#""single_choice""#),
+                ("allowlisted", "false"),
+                ("created_height", "12345"),
+                ("quorum", "null"),
+                ("threshold", "null"),
+                ("reveal_end", "null"),
+                ("deposit", "null"),
+                ("group_weight", "null"),
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_execute_create_poll_invalid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite number?".to_string(),
+            options: vec![
+                "1".to_string(),
+                "2".to_string(),
+                "3".to_string(),
+                "4".to_string(),
+                "5".to_string(),
+                "6".to_string(),
+                "7".to_string(),
+                "8".to_string(),
+                "9".to_string(),
+                "10".to_string(),
+                "11".to_string(),
+            ],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+
+        // Unwrap error to assert failure
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::TooManyOptions {}));
+
+        let create = |poll_id: &str, question: &str, options: &[&str]| ExecuteMsg::CreatePoll {
+            poll_id: Some(poll_id.to_string()),
+            question: question.to_string(),
+            options: options.iter().map(|o| o.to_string()).collect(),
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let long_question = "?".repeat(257);
+        let cases = vec![
+            (
+                create("some_id", "  ", &["Yes", "No"]),
+                ContractError::EmptyQuestion {},
+            ),
+            (
+                create("some_id", "Yes?", &["Yes"]),
+                ContractError::TooFewOptions { min: 2 },
+            ),
+            (
+                create("some_id", "Yes?", &["Yes", ""]),
+                ContractError::EmptyOption {},
+            ),
+            // Options are compared once trimmed
+            (
+                create("some_id", "Yes?", &["Yes", " Yes "]),
+                ContractError::DuplicateOption {
+                    option: "Yes".to_string(),
+                },
+            ),
+            (
+                create("some id!", "Yes?", &["Yes", "No"]),
+                ContractError::InvalidPollId {},
+            ),
+            (
+                create("some_id", &long_question, &["Yes", "No"]),
+                ContractError::TooLong {
+                    field: "question".to_string(),
+                    max: 256,
+                },
+            ),
+        ];
+        for (msg, expected) in cases {
+            let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(err.to_string(), expected.to_string());
+        }
+
+        // Only the admin can change the limits, and only to ones a poll can meet
+        let mut limits = PollLimits {
+            max_options: 11,
+            ..PollLimits::default()
+        };
+        let update = ExecuteMsg::UpdateLimits {
+            limits: limits.clone(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), update).unwrap();
+        limits.min_options = 12;
+        let update = ExecuteMsg::UpdateLimits { limits };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), update).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidLimits {}));
+
+        // Eleven options are fine now
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+    }
+
+    #[test]
+    fn test_execute_create_poll_assigns_id() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create = |poll_id: Option<&str>| ExecuteMsg::CreatePoll {
+            poll_id: poll_id.map(String::from),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+
+        // Leaving out the ID takes the next one in the sequence, zero padded
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), create(None)).unwrap();
+        assert_eq!(res.attributes[1], attr("poll_id", "00000000000000000001"));
+        let data: CreatePollResponse = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(data.poll_id, "00000000000000000001");
+
+        // IDs a client already took are skipped
+        let taken = Some("00000000000000000002");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create(taken)).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), create(None)).unwrap();
+        let data: CreatePollResponse = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(data.poll_id, "00000000000000000003");
+
+        // Padding keeps the sequence in numeric order past 9
+        for _ in 4..=10 {
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), create(None)).unwrap();
+        }
+        let poll_ids = polls()
+            .keys(deps.as_ref().storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        let expected: Vec<_> = (1..=10).map(sequence_poll_id).collect();
+        assert_eq!(poll_ids, expected);
+    }
+
+    #[test]
+    fn test_execute_create_poll_deposit() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create = |poll_id: &str| ExecuteMsg::CreatePoll {
+            poll_id: Some(poll_id.to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: Some(Expiration::AtHeight(env.block.height + 10)),
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: Some(Requirement::Absolute(Uint128::new(1))),
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let creator = mock_info(ADDR2, &coins(100, "ujuno"));
+
+        // Funds are refused while no deposit is asked for
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            create("some_id"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Payment(_)));
+
+        // The admin asks for 100ujuno per poll
+        let msg = ExecuteMsg::UpdateDeposit {
+            deposit: Some(coin(100, "ujuno")),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Not sending the deposit fails
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &coins(50, "ujuno")),
+            create("some_id"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidDeposit { .. }));
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            create("some_id"),
+        )
+        .unwrap();
+
+        let claim = ExecuteMsg::ClaimDeposit {
+            poll_id: "some_id".to_string(),
+        };
+        // Nothing to claim while the poll runs
+        let err = execute(deps.as_mut(), env.clone(), creator.clone(), claim.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotClosed {}));
+
+        // Or when nobody voted
+        let mut closed_env = mock_env();
+        closed_env.block.height += 10;
+        let err = execute(
+            deps.as_mut(),
+            closed_env.clone(),
+            creator.clone(),
+            claim.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::QuorumNotReached {}));
+
+        // Once the poll reached quorum the creator gets the deposit back, once
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            create("other_id"),
+        )
+        .unwrap();
+        let vote = ExecuteMsg::Vote {
+            poll_id: "other_id".to_string(),
+            vote: Choice::Single("Juno".to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), vote).unwrap();
+        let claim = ExecuteMsg::ClaimDeposit {
+            poll_id: "other_id".to_string(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            closed_env.clone(),
+            info.clone(),
+            claim.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: ADDR2.to_string(),
+                amount: coins(100, "ujuno"),
+            })]
+        );
+        let err = execute(deps.as_mut(), closed_env, info.clone(), claim).unwrap_err();
+        assert!(matches!(err, ContractError::NoDeposit {}));
+
+        // Spam loses its deposit to the admin
+        let flag = ExecuteMsg::FlagSpam {
+            poll_id: "some_id".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), creator.clone(), flag.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), flag).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: ADDR1.to_string(),
+                amount: coins(100, "ujuno"),
+            })]
+        );
+
+        // Without a quorum a poll nobody voted on still keeps its deposit
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("no_quorum".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: Some(Expiration::AtHeight(env.block.height + 10)),
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "no_quorum".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+        let claim = ExecuteMsg::ClaimDeposit {
+            poll_id: "no_quorum".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), creator.clone(), claim).unwrap_err();
+        assert!(matches!(err, ContractError::QuorumNotReached {}));
+
+        // Deleting a poll forfeits its deposit to the admin rather than refunding it
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            create("deleted_id"),
+        )
+        .unwrap();
+        let msg = ExecuteMsg::DeletePoll {
+            poll_id: "deleted_id".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: ADDR1.to_string(),
+                amount: coins(100, "ujuno"),
+            })]
+        );
+
+        // Once there is no admin the creator gets it back instead
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            create("orphan_id"),
+        )
+        .unwrap();
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::RenounceAdmin {},
+        )
+        .unwrap();
+        let msg = ExecuteMsg::DeletePoll {
+            poll_id: "orphan_id".to_string(),
+        };
+        let res = execute(deps.as_mut(), env, creator, msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: ADDR2.to_string(),
+                amount: coins(100, "ujuno"),
+            })]
+        );
+    }
+
+    #[test]
+    fn test_execute_update_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmso coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Jnuo".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Only the creator can fix the typos
+        let update = ExecuteMsg::UpdatePoll {
+            poll_id: "some_id".to_string(),
+            question: Some("What's your favourite Cosmos coin?".to_string()),
+            options: Some(vec!["Cosmos Hub".to_string(), "Juno".to_string()]),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), update.clone()).unwrap();
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        assert_eq!(poll.question, "What's your favourite Cosmos coin?");
+        let options: Vec<_> = poll.options.iter().map(|o| o.text.as_str()).collect();
+        assert_eq!(options, vec!["Cosmos Hub", "Juno"]);
+        // The event carries the new wording
+        assert_eq!(
+            res.events,
+            vec![Event::new("update_poll").add_attributes(vec![
+                ("poll_id", "some_id"),
+                ("creator", ADDR1),
+                ("question", "What's your favourite Cosmos coin?"),
+                ("option", "Cosmos Hub"),
+                ("msgs", "[]"),
+                ("option", "Juno"),
+                ("msgs", "[]"),
+            ])]
+        );
+
+        // The same rules as creating a poll apply
+        let msg = ExecuteMsg::UpdatePoll {
+            poll_id: "some_id".to_string(),
+            question: None,
+            options: Some(vec!["Juno".to_string(), "Juno".to_string()]),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateOption { .. }));
+
+        // After the first vote the poll is fixed
+        let vote = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Juno".to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), vote).unwrap();
+        let err = execute(deps.as_mut(), env, info, update).unwrap_err();
+        assert!(matches!(err, ContractError::PollHasVotes {}));
+    }
+
+    #[test]
+    fn test_execute_vote_valid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Create the poll
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec![
+                "Cosmos Hub".to_string(),
+                "Juno".to_string(),
+                "Osmosis".to_string(),
+            ],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Create the vote, first time voting
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Juno".to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Funds sent to a poll that does not lock them are refused
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Osmosis".to_string()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR1, &coins(100, "ujuno")),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Payment(_)));
+
+        // Change the vote
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "vote"),
+                attr("poll_id", "some_id"),
+                attr("voter", ADDR1)
+            ]
+        );
+        // The event has both choices and the tallies after the change
+        assert_eq!(
+            res.events,
+            vec![Event::new("vote").add_attributes(vec![
+                ("poll_id", "some_id"),
+                ("voter", ADDR1),
+                ("option", r#""Osmosis""#),
+                ("previous_option", r#""Juno""#),
+                ("weight", "1"),
+                (
+                    "tallies",
+                    r#"[["Cosmos Hub","0"],["Juno","0"],["Osmosis","1"]]"#,
+                ),
+                ("turnout", "1"),
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_execute_vote_invalid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Create the vote, some_id poll is not created yet.
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Juno".to_string()),
+        };
+        // Unwrap to assert error
+        let _err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+
+        // Create the poll
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec![
+                "Cosmos Hub".to_string(),
+                "Juno".to_string(),
+                "Osmosis".to_string(),
+            ],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Vote on a now existing poll but the option "DVPN" does not exist
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("DVPN".to_string()),
+        };
+
+        let _err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    }
+
+    #[test]
+    fn test_execute_vote_outside_poll_period() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Create a poll that opens in 10 blocks and closes in 20 blocks
+        let height = env.block.height;
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec![
+                "Cosmos Hub".to_string(),
+                "Juno".to_string(),
+                "Osmosis".to_string(),
+            ],
+            start: Some(Scheduled::AtHeight(height + 10)),
+            end: Some(Expiration::AtHeight(height + 20)),
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Juno".to_string()),
+        };
+
+        // Too early, the poll is still pending
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotStarted {}));
+
+        // Inside the voting period
+        env.block.height = height + 10;
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        let bin = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Poll {
+                poll_id: "some_id".to_string(),
+            },
+        )
+        .unwrap();
+        let res: PollResponse = from_binary(&bin).unwrap();
+        assert_eq!(res.status, Some(PollStatus::Open));
+
+        // Too late, the poll has closed
+        env.block.height = height + 20;
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollClosed {}));
+        let bin = query(
+            deps.as_ref(),
+            env,
+            QueryMsg::Poll {
+                poll_id: "some_id".to_string(),
+            },
+        )
+        .unwrap();
+        let res: PollResponse = from_binary(&bin).unwrap();
+        assert_eq!(res.status, Some(PollStatus::Closed));
+    }
+
+    #[test]
+    fn test_execute_create_poll_invalid_period() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // The poll would end before it starts
+        let height = env.block.height;
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: Some(Scheduled::AtHeight(height + 20)),
+            end: Some(Expiration::AtHeight(height + 10)),
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPollPeriod {}));
+
+        // The poll would already be closed
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: Some(Expiration::AtHeight(height)),
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPollPeriod {}));
+    }
+
+    #[test]
+    fn test_execute_close_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract, ADDR1 is the admin
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // ADDR2 creates a poll
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+
+        // Someone else cannot close it
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR3, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // The admin can
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+
+        // Closing twice fails
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollClosed {}));
+
+        // No more votes are accepted
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Juno".to_string()),
+        };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollClosed {}));
+    }
+
+    #[test]
+    fn test_execute_delete_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract, ADDR1 is the admin
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // ADDR2 creates a poll and votes on it
+        let creator = mock_info(ADDR2, &[]);
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Juno".to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+
+        // Someone else cannot delete it
+        let msg = ExecuteMsg::DeletePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR3, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // The creator can
+        let _res = execute(deps.as_mut(), env.clone(), creator, msg).unwrap();
+
+        // The poll and its ballots are gone
+        let msg = QueryMsg::Poll {
+            poll_id: "some_id".to_string(),
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: PollResponse = from_binary(&bin).unwrap();
+        assert!(res.poll.is_none());
+        let msg = QueryMsg::Vote {
+            poll_id: "some_id".to_string(),
+            address: ADDR2.to_string(),
+        };
+        let bin = query(deps.as_ref(), env, msg).unwrap();
+        let res: VoteResponse = from_binary(&bin).unwrap();
+        assert!(res.vote.is_none());
+    }
+
+    #[test]
+    fn test_execute_vote_native_token_weighted() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // Create a poll weighted by the ujuno sent with each vote
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: Some(VotingPower::NativeToken {
+                denom: "ujuno".to_string(),
+            }),
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+
+        let vote = |option: &str| ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single(option.to_string()),
+        };
+
+        // No funds means no voting power
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR1, &[]),
+            vote("Juno"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoVotingPower {}));
+
+        // The wrong denom is rejected
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR1, &coins(100, "uatom")),
+            vote("Juno"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Payment(_)));
+
+        // ADDR1 locks 100 and ADDR2 locks 50
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR1, &coins(100, "ujuno")),
+            vote("Juno"),
+        )
+        .unwrap();
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &coins(50, "ujuno")),
+            vote("Cosmos Hub"),
+        )
+        .unwrap();
+
+        // ADDR1 switches and tops up by 10, their whole weight moves over
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR1, &coins(10, "ujuno")),
+            vote("Cosmos Hub"),
+        )
+        .unwrap();
+
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        assert_eq!(poll.options[0].votes, Uint128::new(160));
+        assert_eq!(poll.options[1].votes, Uint128::zero());
+        let ballot = BALLOTS
+            .load(deps.as_ref().storage, (Addr::unchecked(ADDR1), "some_id"))
+            .unwrap();
+        assert_eq!(ballot.weight, Uint128::new(110));
+
+        // Deleting the poll refunds the locked funds
+        let msg = ExecuteMsg::DeletePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let res = execute(deps.as_mut(), env, mock_info(ADDR1, &[]), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: ADDR1.to_string(),
+                    amount: coins(110, "ujuno"),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: ADDR2.to_string(),
+                    amount: coins(50, "ujuno"),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_execute_vote_cw20_weighted() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Create a poll weighted by the CW20 token
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: Some(VotingPower::Cw20Token {
+                address: Addr::unchecked(TOKEN),
+            }),
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let receive = |voter: &str, amount: u128, option: &str| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: voter.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::Vote {
+                    poll_id: "some_id".to_string(),
+                    vote: Choice::Single(option.to_string()),
+                })
+                .unwrap(),
+            })
+        };
+
+        // Tokens from another contract are rejected
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other_token", &[]),
+            receive(ADDR2, 100, "Juno"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidToken {}));
+
+        // ADDR2 locks 100 tokens on Juno
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(TOKEN, &[]),
+            receive(ADDR2, 100, "Juno"),
+        )
+        .unwrap();
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        assert_eq!(poll.options[1].votes, Uint128::new(100));
+
+        // A plain vote moves the locked weight without adding to it
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Cosmos Hub".to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        assert_eq!(poll.options[0].votes, Uint128::new(100));
+        assert_eq!(poll.options[1].votes, Uint128::zero());
+
+        // Tokens cannot be withdrawn while the poll is open
+        let withdraw = ExecuteMsg::Withdraw {
+            poll_id: "some_id".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            withdraw.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollNotClosed {}));
+
+        // Once closed they can, exactly once
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            withdraw.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: TOKEN.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: ADDR2.to_string(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+        let err = execute(deps.as_mut(), env, mock_info(ADDR2, &[]), withdraw).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+    }
+
+    #[test]
+    fn test_execute_vote_approval() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // More choices than options is rejected
+        let options = vec![
+            "Cosmos Hub".to_string(),
+            "Juno".to_string(),
+            "Osmosis".to_string(),
+        ];
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "Which Cosmos coins do you like?".to_string(),
+            options: options.clone(),
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: Some(PollKind::Approval { max_choices: 4 }),
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMaxChoices {}));
+
+        // Voters may approve of up to two options
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "Which Cosmos coins do you like?".to_string(),
+            options,
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: Some(PollKind::Approval { max_choices: 2 }),
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let vote = |options: &[&str]| ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Multiple(options.iter().map(|o| o.to_string()).collect()),
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            vote(&["Cosmos Hub", "Juno", "Osmosis"]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::TooManyChoices {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            vote(&["Juno", "Juno"]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateChoice {}));
+
+        // ADDR1 approves of Juno and Osmosis, ADDR2 of Juno only
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            vote(&["Juno", "Osmosis"]),
+        )
+        .unwrap();
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            vote(&["Juno"]),
+        )
+        .unwrap();
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        let votes: Vec<_> = poll.options.iter().map(|o| o.votes.u128()).collect();
+        assert_eq!(votes, vec![0, 2, 1]);
+
+        // ADDR1 changes to Cosmos Hub, both old selections are revoked
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Cosmos Hub".to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        let votes: Vec<_> = poll.options.iter().map(|o| o.votes.u128()).collect();
+        assert_eq!(votes, vec![1, 1, 0]);
+
+        // An option every voter approves of has all of their support, however many
+        // other options they approve of too
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("threshold_id".to_string()),
+            question: "Which Cosmos coins do you like?".to_string(),
+            options: vec![
+                "Cosmos Hub".to_string(),
+                "Juno".to_string(),
+                "Osmosis".to_string(),
+            ],
+            start: None,
+            end: Some(Expiration::AtHeight(env.block.height + 10)),
+            voting_power: None,
+            kind: Some(PollKind::Approval { max_choices: 2 }),
+            voters: None,
+            quorum: None,
+            threshold: Some(Requirement::Percentage(Decimal::percent(51))),
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        for (voter, options) in [
+            (ADDR1, vec!["Juno", "Cosmos Hub"]),
+            (ADDR2, vec!["Juno", "Osmosis"]),
+            (ADDR3, vec!["Juno", "Cosmos Hub"]),
+        ] {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "threshold_id".to_string(),
+                vote: Choice::Multiple(options.iter().map(|o| o.to_string()).collect()),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+        let mut closed_env = env;
+        closed_env.block.height += 10;
+        let msg = QueryMsg::PollResult {
+            poll_id: "threshold_id".to_string(),
+        };
+        let bin = query(deps.as_ref(), closed_env, msg).unwrap();
+        let res: PollResultResponse = from_binary(&bin).unwrap();
+        assert_eq!(res.outcome, PollOutcome::Passed);
+        assert_eq!(res.winner, Some("Juno".to_string()));
+        assert_eq!(res.total_votes, Uint128::new(6));
+    }
+
+    #[test]
+    fn test_execute_vote_multiple_on_single_choice() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Create a single choice poll
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // A list of options is not accepted
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Multiple(vec!["Cosmos Hub".to_string(), "Juno".to_string()]),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidChoice {}));
+
+        // Clients can keep sending a plain string
+        let msg: ExecuteMsg =
+            from_slice(br#"{"vote":{"poll_id":"some_id","vote":"Juno"}}"#).unwrap();
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+    }
+
+    #[test]
+    fn test_query_ranked_result() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Create a ranked choice poll
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "Rank the Cosmos coins".to_string(),
+            options: vec![
+                "Cosmos Hub".to_string(),
+                "Juno".to_string(),
+                "Osmosis".to_string(),
+            ],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: Some(PollKind::Ranked),
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let ballots: [(&str, &[&str]); 5] = [
+            (ADDR1, &["Cosmos Hub", "Juno"]),
+            (ADDR2, &["Juno", "Cosmos Hub"]),
+            (ADDR3, &["Osmosis", "Juno"]),
+            ("addr4", &["Cosmos Hub"]),
+            ("addr5", &["Juno", "Osmosis"]),
+        ];
+        for (voter, ranking) in ballots {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "some_id".to_string(),
+                vote: Choice::Multiple(ranking.iter().map(|o| o.to_string()).collect()),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+
+        // The poll keeps reporting first preferences
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        let votes: Vec<_> = poll.options.iter().map(|o| o.votes.u128()).collect();
+        assert_eq!(votes, vec![2, 2, 1]);
+
+        // Osmosis goes out first and its ballot moves to Juno, which then has a majority
+        let msg = QueryMsg::RankedResult {
+            poll_id: "some_id".to_string(),
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: RankedResultResponse = from_binary(&bin).unwrap();
+        assert_eq!(res.rounds.len(), 2);
+        assert_eq!(res.rounds[0].eliminated, Some("Osmosis".to_string()));
+        let votes: Vec<_> = res.rounds[1]
+            .tallies
+            .iter()
+            .map(|o| o.votes.u128())
+            .collect();
+        assert_eq!(votes, vec![2, 3]);
+        assert_eq!(res.rounds[1].eliminated, None);
+        assert_eq!(res.winner, Some("Juno".to_string()));
+
+        // Executing needs the count done in batches once the poll has closed
+        let tally = ExecuteMsg::TallyRanked {
+            poll_id: "some_id".to_string(),
+            limit: Some(2),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            tally.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollNotClosed {}));
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+        let execute_msg = ExecuteMsg::Execute {
+            poll_id: "some_id".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            execute_msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotTallied {}));
+
+        // Five ballots two at a time take three calls for each of the two rounds
+        for call in 1..=6 {
+            let res = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(ADDR2, &[]),
+                tally.clone(),
+            )
+            .unwrap();
+            let finished = res
+                .attributes
+                .iter()
+                .any(|a| a.key == "finished" && a.value == "true");
+            assert_eq!(finished, call == 6);
+        }
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), tally).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyTallied {}));
+        let counted = RANKED_TALLIES
+            .load(deps.as_ref().storage, "some_id")
+            .unwrap();
+        let votes: Vec<_> = counted.tallies.iter().map(|o| o.votes.u128()).collect();
+        assert_eq!(votes, vec![2, 3]);
+
+        let res = execute(deps.as_mut(), env, mock_info(ADDR2, &[]), execute_msg).unwrap();
+        assert!(res.attributes.contains(&attr("option", "Juno")));
+    }
+
+    #[test]
+    fn test_execute_vote_quadratic() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Each voter gets 10 credits
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "How much do you like each Cosmos coin?".to_string(),
+            options: vec![
+                "Cosmos Hub".to_string(),
+                "Juno".to_string(),
+                "Osmosis".to_string(),
+            ],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: Some(PollKind::Quadratic { credits: 10 }),
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let vote = |allocations: &[(&str, u64)]| ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Allocations(
+                allocations
+                    .iter()
+                    .map(|(option, votes)| Allocation {
+                        option: option.to_string(),
+                        votes: *votes,
+                    })
+                    .collect(),
+            ),
+        };
+
+        // 3 votes on Juno and 2 on Osmosis cost 13 credits
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            vote(&[("Juno", 3), ("Osmosis", 2)]),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InsufficientCredits {
+                cost: 13,
+                credits: 10
+            }
+        ));
+
+        // 3 on Juno and 1 on Osmosis fit the budget exactly
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            vote(&[("Juno", 3), ("Osmosis", 1)]),
+        )
+        .unwrap();
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        let votes: Vec<_> = poll.options.iter().map(|o| o.votes.u128()).collect();
+        assert_eq!(votes, vec![0, 3, 1]);
+
+        // Revoting refunds the old allocation before spending the credits again
+        let msg: ExecuteMsg = from_slice(
+            br#"{"vote":{"poll_id":"some_id","vote":[{"option":"Juno","votes":1},{"option":"Osmosis","votes":3}]}}"#,
+        )
+        .unwrap();
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        let votes: Vec<_> = poll.options.iter().map(|o| o.votes.u128()).collect();
+        assert_eq!(votes, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn test_execute_vote_allowlist() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Only ADDR2 may vote on this poll
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: Some(vec![ADDR2.to_string()]),
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let vote = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Juno".to_string()),
+        };
+        let is_eligible = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, address: &str| {
+            let msg = QueryMsg::IsEligible {
+                poll_id: "some_id".to_string(),
+                address: address.to_string(),
+            };
+            let bin = query(deps.as_ref(), mock_env(), msg).unwrap();
+            from_binary::<EligibleResponse>(&bin).unwrap().eligible
+        };
+
+        // ADDR3 is not on the list
+        assert!(!is_eligible(&deps, ADDR3));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR3, &[]),
+            vote.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+
+        // ADDR2 is
+        assert!(is_eligible(&deps, ADDR2));
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            vote.clone(),
+        )
+        .unwrap();
+
+        // Only the creator manages the list
+        let msg = ExecuteMsg::AddVoters {
+            poll_id: "some_id".to_string(),
+            voters: vec![ADDR3.to_string()],
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert!(is_eligible(&deps, ADDR3));
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR3, &[]),
+            vote.clone(),
+        )
+        .unwrap();
+
+        // Removed voters cannot vote again
+        let msg = ExecuteMsg::RemoveVoters {
+            poll_id: "some_id".to_string(),
+            voters: vec![ADDR2.to_string()],
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert!(!is_eligible(&deps, ADDR2));
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), vote).unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+
+        // Polls open to everyone have no list to manage
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("open_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::AddVoters {
+            poll_id: "open_id".to_string(),
+            voters: vec![ADDR3.to_string()],
+        };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotAllowlisted {}));
+    }
+
+    #[test]
+    fn test_execute_vote_cw4_group() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // The group has ADDR1 with weight 5 and ADDR2 with weight 2 at the creation height
+        let snapshot = env.block.height;
+        let group = move |total: u64| {
+            move |query: &WasmQuery| match query {
+                // The total weight is read straight from the group's storage
+                WasmQuery::Raw { contract_addr, .. } if contract_addr == GROUP => {
+                    SystemResult::Ok(ContractResult::Ok(to_binary(&total).unwrap()))
+                }
+                WasmQuery::Smart { contract_addr, msg } if contract_addr == GROUP => {
+                    let res = match from_binary(msg).unwrap() {
+                        Cw4QueryMsg::Member { addr, at_height } => {
+                            let weight = match (addr.as_str(), at_height) {
+                                (ADDR1, Some(height)) if height == snapshot => Some(5),
+                                (ADDR2, Some(height)) if height == snapshot => Some(2),
+                                _ => None,
+                            };
+                            to_binary(&MemberResponse { weight })
+                        }
+                        _ => panic!("unexpected query"),
+                    };
+                    SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+                }
+                _ => SystemResult::Err(SystemError::NoSuchContract {
+                    addr: "not a group".to_string(),
+                }),
+            }
+        };
+        deps.querier.update_wasm(group(7));
+
+        // Something which is not a group is rejected
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: Some(VotingPower::Cw4Group {
+                address: Addr::unchecked("not_a_group"),
+            }),
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: Some(VotingPower::Cw4Group {
+                address: Addr::unchecked(GROUP),
+            }),
+            kind: None,
+            voters: None,
+            quorum: Some(Requirement::Percentage(Decimal::percent(50))),
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // Members joining later do not count towards the quorum either
+        deps.querier.update_wasm(group(70));
+
+        // Voting happens a few blocks later, the snapshot still applies
+        let mut env = env;
+        env.block.height += 5;
+        let vote = |option: &str| ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single(option.to_string()),
+        };
+
+        // Non members are rejected, and reported as such
+        let is_eligible = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, address: &str| {
+            let msg = QueryMsg::IsEligible {
+                poll_id: "some_id".to_string(),
+                address: address.to_string(),
+            };
+            let bin = query(deps.as_ref(), mock_env(), msg).unwrap();
+            from_binary::<EligibleResponse>(&bin).unwrap().eligible
+        };
+        assert!(is_eligible(&deps, ADDR1));
+        assert!(!is_eligible(&deps, ADDR3));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR3, &[]),
+            vote("Juno"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+
+        // Members vote with their weight
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR1, &[]),
+            vote("Juno"),
+        )
+        .unwrap();
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            vote("Cosmos Hub"),
+        )
+        .unwrap();
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        let votes: Vec<_> = poll.options.iter().map(|o| o.votes.u128()).collect();
+        assert_eq!(votes, vec![2, 5]);
+        let msg = QueryMsg::PollResult {
+            poll_id: "some_id".to_string(),
+        };
+        let bin = query(deps.as_ref(), env, msg).unwrap();
+        let res: PollResultResponse = from_binary(&bin).unwrap();
+        assert_eq!(res.eligible_weight, Some(Uint128::new(7)));
+    }
+
+    #[test]
+    fn test_query_poll_result() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Half of the three voters must turn out and the winner needs 60% of the votes
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: Some(Expiration::AtHeight(env.block.height + 10)),
+            voting_power: None,
+            kind: None,
+            voters: Some(vec![
+                ADDR1.to_string(),
+                ADDR2.to_string(),
+                ADDR3.to_string(),
+            ]),
+            quorum: Some(Requirement::Percentage(Decimal::percent(50))),
+            threshold: Some(Requirement::Percentage(Decimal::percent(60))),
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let mut closed_env = mock_env();
+        closed_env.block.height += 10;
+        let result = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, env: &Env| {
+            let msg = QueryMsg::PollResult {
+                poll_id: "some_id".to_string(),
+            };
+            let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+            from_binary::<PollResultResponse>(&bin).unwrap()
+        };
+        let vote = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, voter: &str, option: &str| {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "some_id".to_string(),
+                vote: Choice::Single(option.to_string()),
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(voter, &[]), msg).unwrap();
+        };
+
+        // Nothing is decided while the poll is open
+        vote(&mut deps, ADDR1, "Juno");
+        assert_eq!(result(&deps, &env).outcome, PollOutcome::Pending);
+
+        // One voter out of three is not enough
+        let res = result(&deps, &closed_env);
+        assert_eq!(res.outcome, PollOutcome::NoQuorum);
+        assert_eq!(res.turnout, Uint128::new(1));
+        assert_eq!(res.eligible_weight, Some(Uint128::new(3)));
+
+        // A tie has no winner
+        vote(&mut deps, ADDR2, "Cosmos Hub");
+        let res = result(&deps, &closed_env);
+        assert_eq!(res.outcome, PollOutcome::Rejected);
+        assert_eq!(res.winner, None);
+
+        // Two thirds clears the threshold, changing a vote does not add to the turnout
+        vote(&mut deps, ADDR3, "Juno");
+        vote(&mut deps, ADDR1, "Juno");
+        let res = result(&deps, &closed_env);
+        assert_eq!(
+            res,
+            PollResultResponse {
+                outcome: PollOutcome::Passed,
+                winner: Some("Juno".to_string()),
+                winning_votes: Uint128::new(2),
+                margin: Uint128::new(1),
+                total_votes: Uint128::new(3),
+                turnout: Uint128::new(3),
+                eligible_weight: Some(Uint128::new(3)),
+            }
+        );
+
+        // A quorum percentage is meaningless when anyone can vote
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("open_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: Some(Requirement::Percentage(Decimal::percent(50))),
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownEligibleWeight {}));
+
+        // And no share can be above 100%
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("open_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: Some(Requirement::Absolute(Uint128::new(2))),
+            threshold: Some(Requirement::Percentage(Decimal::percent(150))),
+            msgs: None,
+            reveal_end: None,
+        };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPercentage {}));
+    }
+
+    #[test]
+    fn test_execute_execute() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Paying out the treasury only happens if "Yes" wins
+        let payout: CosmosMsg = BankMsg::Send {
+            to_address: ADDR2.to_string(),
+            amount: coins(100, "ujuno"),
+        }
+        .into();
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "Should we fund the grant?".to_string(),
+            options: vec!["Yes".to_string(), "No".to_string()],
+            start: None,
+            end: Some(Expiration::AtHeight(env.block.height + 10)),
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: Some(Requirement::Absolute(Uint128::new(2))),
+            threshold: None,
+            msgs: Some(vec![vec![payout.clone()], vec![]]),
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Cannot execute before the end
+        let msg = ExecuteMsg::Execute {
+            poll_id: "some_id".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotClosed {}));
+
+        for voter in [ADDR1, ADDR2] {
+            let vote = ExecuteMsg::Vote {
+                poll_id: "some_id".to_string(),
+                vote: Choice::Single("Yes".to_string()),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), vote).unwrap();
+        }
+
+        // Anyone can execute once it passed, the winning messages are dispatched
+        let mut closed_env = mock_env();
+        closed_env.block.height += 10;
+        let res = execute(
+            deps.as_mut(),
+            closed_env.clone(),
+            mock_info(ADDR3, &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(payout)]);
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        assert_eq!(poll.status(&closed_env.block), PollStatus::Executed);
+
+        // But only once
+        let err = execute(deps.as_mut(), closed_env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyExecuted {}));
+
+        // A poll short of its quorum does not pass
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("other_id".to_string()),
+            question: "Should we fund the grant?".to_string(),
+            options: vec!["Yes".to_string(), "No".to_string()],
+            start: None,
+            end: Some(Expiration::AtHeight(env.block.height + 10)),
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: Some(Requirement::Absolute(Uint128::new(2))),
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let vote = ExecuteMsg::Vote {
+            poll_id: "other_id".to_string(),
+            vote: Choice::Single("Yes".to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), vote).unwrap();
+        let msg = ExecuteMsg::Execute {
+            poll_id: "other_id".to_string(),
+        };
+        let err = execute(deps.as_mut(), closed_env, info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotPassed {}));
+
+        // Every option needs its own list of messages
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("bad_id".to_string()),
+            question: "Should we fund the grant?".to_string(),
+            options: vec!["Yes".to_string(), "No".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: Some(vec![vec![]]),
+            reveal_end: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMsgs {}));
+
+        // Anyone else trying to pay themselves from the treasury is turned away
+        let info = mock_info(ADDR2, &[]);
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("drain".to_string()),
+            question: "Should we pay ADDR2?".to_string(),
+            options: vec!["Yes".to_string(), "No".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: Some(vec![
+                vec![BankMsg::Send {
+                    to_address: ADDR2.to_string(),
+                    amount: coins(100, "ujuno"),
+                }
+                .into()],
+                vec![],
+            ]),
+            reveal_end: None,
+        };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn test_execute_reveal() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Votes are sealed for 10 blocks then revealed over the next 10
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: Some(Expiration::AtHeight(env.block.height + 10)),
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: Some(Expiration::AtHeight(env.block.height + 20)),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Open votes are refused
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Juno".to_string()),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidChoice {}));
+
+        // The commitment is the hash of the poll ID, voter, vote's JSON and salt
+        let seal = |vote: &[u8]| Choice::Sealed {
+            commitment: Binary::from(Sha256::digest(vote).to_vec()),
+        };
+        // ADDR3 copies ADDR1's commitment hoping to replay their reveal
+        for (voter, sealed) in [
+            (ADDR1, seal(br#"some_idaddr1"Juno"salt1"#)),
+            (ADDR2, seal(br#"some_idaddr2"Cosmos Hub"salt2"#)),
+            (ADDR3, seal(br#"some_idaddr1"Juno"salt1"#)),
+        ] {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "some_id".to_string(),
+                vote: sealed,
+            };
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+        // Nothing is counted yet
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        let votes: Vec<_> = poll.options.iter().map(|o| o.votes.u128()).collect();
+        assert_eq!(votes, vec![0, 0]);
+
+        let reveal = |salt: &str| ExecuteMsg::Reveal {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Juno".to_string()),
+            salt: salt.to_string(),
+        };
+        // Votes cannot be revealed while the poll is open
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), reveal("salt1")).unwrap_err();
+        assert!(matches!(err, ContractError::NotRevealPeriod {}));
+
+        let mut reveal_env = mock_env();
+        reveal_env.block.height += 10;
+        // Nor can anyone vote once the reveal period starts
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: seal(br#"some_idaddr3"Juno"salt3"#),
+        };
+        let err = execute(
+            deps.as_mut(),
+            reveal_env.clone(),
+            mock_info(ADDR4, &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollClosed {}));
+
+        // The wrong salt does not open the seal
+        let err = execute(
+            deps.as_mut(),
+            reveal_env.clone(),
+            info.clone(),
+            reveal("salt2"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidReveal {}));
+
+        // Pausing the contract or freezing the poll does not stop reveals
+        let _res = execute(
+            deps.as_mut(),
+            reveal_env.clone(),
+            info.clone(),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+        let msg = ExecuteMsg::FreezePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), reveal_env.clone(), info.clone(), msg).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            reveal_env.clone(),
+            info.clone(),
+            reveal("salt1"),
+        )
+        .unwrap();
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        let votes: Vec<_> = poll.options.iter().map(|o| o.votes.u128()).collect();
+        assert_eq!(votes, vec![0, 1]);
+        // The reveal logs the counted vote and the new tallies
+        assert_eq!(
+            res.events,
+            vec![Event::new("reveal").add_attributes(vec![
+                ("poll_id", "some_id"),
+                ("voter", ADDR1),
+                ("option", r#""Juno""#),
+                ("weight", "1"),
+                ("tallies", r#"[["Cosmos Hub","0"],["Juno","1"]]"#),
+                ("turnout", "1"),
+            ])]
+        );
+        // Revealing twice does nothing
+        let err = execute(
+            deps.as_mut(),
+            reveal_env.clone(),
+            info.clone(),
+            reveal("salt1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToReveal {}));
+
+        // The copied commitment cannot be opened by anyone but ADDR1
+        let err = execute(
+            deps.as_mut(),
+            reveal_env.clone(),
+            mock_info(ADDR3, &[]),
+            reveal("salt1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidReveal {}));
+
+        // Only ADDR2's and the copier's votes are still sealed
+        let msg = QueryMsg::UnrevealedVotes {
+            poll_id: "some_id".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let bin = query(deps.as_ref(), reveal_env.clone(), msg).unwrap();
+        let res: VotesResponse = from_binary(&bin).unwrap();
+        let voters: Vec<_> = res.votes.iter().map(|v| v.voter.as_str()).collect();
+        assert_eq!(voters, vec![ADDR2, ADDR3]);
+
+        // After the reveal period the seal stays closed for good
+        let mut closed_env = mock_env();
+        closed_env.block.height += 20;
+        let msg = ExecuteMsg::Reveal {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Cosmos Hub".to_string()),
+            salt: "salt2".to_string(),
+        };
+        let err = execute(deps.as_mut(), closed_env, mock_info(ADDR2, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NotRevealPeriod {}));
+    }
+
+    #[test]
+    fn test_execute_delegate() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // ADDR2 delegates to ADDR1 on every poll, ADDR3 only on this one
+        let msg = ExecuteMsg::Delegate {
+            to: ADDR1.to_string(),
+            poll_id: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+        let msg = ExecuteMsg::Delegate {
+            to: ADDR1.to_string(),
+            poll_id: Some("some_id".to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR3, &[]), msg).unwrap();
+        let delegate = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, poll_id: Option<&str>| {
+            let msg = QueryMsg::Delegation {
+                address: ADDR3.to_string(),
+                poll_id: poll_id.map(String::from),
+            };
+            let bin = query(deps.as_ref(), mock_env(), msg).unwrap();
+            from_binary::<DelegationResponse>(&bin).unwrap().delegate
+        };
+        assert_eq!(
+            delegate(&deps, Some("some_id")),
+            Some(Addr::unchecked(ADDR1))
+        );
+        assert_eq!(delegate(&deps, None), None);
+
+        let vote = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, voter: &str, option: &str| {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "some_id".to_string(),
+                vote: Choice::Single(option.to_string()),
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(voter, &[]), msg).unwrap();
+        };
+        let votes = |deps: &cosmwasm_std::OwnedDeps<_, _, _>| {
+            let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+            let votes: Vec<_> = poll.options.iter().map(|o| o.votes.u128()).collect();
+            (votes, poll.turnout.u128())
+        };
+
+        // ADDR1 votes with all three
+        vote(&mut deps, ADDR1, "Juno");
+        assert_eq!(votes(&deps), (vec![0, 3], 3));
+
+        // ADDR2 voting directly takes their weight back from ADDR1
+        vote(&mut deps, ADDR2, "Cosmos Hub");
+        assert_eq!(votes(&deps), (vec![1, 2], 3));
+
+        // ADDR1 changing their vote still carries ADDR3 but not ADDR2
+        vote(&mut deps, ADDR1, "Cosmos Hub");
+        assert_eq!(votes(&deps), (vec![3, 0], 3));
+        let ballot = BALLOTS
+            .load(deps.as_ref().storage, (Addr::unchecked(ADDR1), "some_id"))
+            .unwrap();
+        assert_eq!(ballot.weight, Uint128::new(2));
+
+        // Nobody can delegate to themselves
+        let msg = ExecuteMsg::Delegate {
+            to: ADDR1.to_string(),
+            poll_id: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::SelfDelegation {}));
+
+        // Undelegating only works on an existing delegation
+        let msg = ExecuteMsg::Undelegate {
+            poll_id: Some("some_id".to_string()),
+        };
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR3, &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(delegate(&deps, Some("some_id")), None);
+        let err = execute(deps.as_mut(), env, mock_info(ADDR3, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NotDelegated {}));
     }
 
     #[test]
-    fn test_execute_create_poll_valid() {
+    fn test_execute_delegate_limit() {
         let mut deps = mock_dependencies();
         let env = mock_env();
         let info = mock_info(ADDR1, &[]);
@@ -239,23 +4658,73 @@ mod tests {
         let msg = InstantiateMsg { admin: None };
         let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-        // New execute msg
         let msg = ExecuteMsg::CreatePoll {
-            poll_id: "some_id".to_string(),
+            poll_id: Some("some_id".to_string()),
             question: "What's your favourite Cosmos coin?".to_string(),
-            options: vec![
-                "Cosmos Hub".to_string(),
-                "Juno".to_string(),
-                "Osmosis".to_string(),
-            ],
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
         };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-        // Unwrap to assert success
-        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+        // ADDR1 takes on as many delegators as allowed
+        let delegate = ExecuteMsg::Delegate {
+            to: ADDR1.to_string(),
+            poll_id: None,
+        };
+        for i in 0..MAX_DELEGATORS {
+            let delegator = format!("delegator{:02}", i);
+            let _res = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(&delegator, &[]),
+                delegate.clone(),
+            )
+            .unwrap();
+        }
+        // A new one is refused, an existing one can still confirm their delegation
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            delegate.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::TooManyDelegators {}));
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("delegator00", &[]),
+            delegate,
+        )
+        .unwrap();
+
+        // Voting again drops the weight picked up before by its own prefix, then picks it up again
+        let vote = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Juno".to_string()),
+        };
+        for _ in 0..2 {
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), vote.clone()).unwrap();
+            let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+            assert_eq!(poll.turnout.u128(), MAX_DELEGATORS as u128 + 1);
+            let picked_up = DELEGATE_VOTES
+                .prefix(("some_id", Addr::unchecked(ADDR1)))
+                .keys(deps.as_ref().storage, None, None, Order::Ascending)
+                .count();
+            assert_eq!(picked_up, MAX_DELEGATORS);
+        }
     }
 
     #[test]
-    fn test_execute_create_poll_invalid() {
+    fn test_execute_undelegate_revokes_weight() {
         let mut deps = mock_dependencies();
         let env = mock_env();
         let info = mock_info(ADDR1, &[]);
@@ -264,29 +4733,135 @@ mod tests {
         let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
         let msg = ExecuteMsg::CreatePoll {
-            poll_id: "some_id".to_string(),
-            question: "What's your favourite number?".to_string(),
-            options: vec![
-                "1".to_string(),
-                "2".to_string(),
-                "3".to_string(),
-                "4".to_string(),
-                "5".to_string(),
-                "6".to_string(),
-                "7".to_string(),
-                "8".to_string(),
-                "9".to_string(),
-                "10".to_string(),
-                "11".to_string(),
-            ],
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
         };
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
 
-        // Unwrap error to assert failure
-        let _err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        let delegate = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>,
+                        delegator: &str,
+                        to: &str,
+                        poll_id: Option<&str>| {
+            let msg = ExecuteMsg::Delegate {
+                to: to.to_string(),
+                poll_id: poll_id.map(String::from),
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(delegator, &[]), msg).unwrap();
+        };
+        let undelegate = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>,
+                          delegator: &str,
+                          poll_id: Option<&str>| {
+            let msg = ExecuteMsg::Undelegate {
+                poll_id: poll_id.map(String::from),
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(delegator, &[]), msg).unwrap()
+        };
+        let vote = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, voter: &str, option: &str| {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "some_id".to_string(),
+                vote: Choice::Single(option.to_string()),
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(voter, &[]), msg).unwrap();
+        };
+        let votes = |deps: &cosmwasm_std::OwnedDeps<_, _, _>| {
+            let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+            let votes: Vec<_> = poll.options.iter().map(|o| o.votes.u128()).collect();
+            (votes, poll.turnout.u128())
+        };
+
+        // ADDR1 votes with ADDR2's global and ADDR3's per poll delegation
+        delegate(&mut deps, ADDR2, ADDR1, None);
+        delegate(&mut deps, ADDR3, ADDR1, Some("some_id"));
+        vote(&mut deps, ADDR1, "Juno");
+        assert_eq!(votes(&deps), (vec![0, 3], 3));
+
+        // Undelegating from the poll takes ADDR3's weight back out, and logs the new tallies
+        let res = undelegate(&mut deps, ADDR3, Some("some_id"));
+        assert_eq!(votes(&deps), (vec![0, 2], 2));
+        assert_eq!(
+            res.events,
+            vec![Event::new("revoke_delegation").add_attributes(vec![
+                ("poll_id", "some_id"),
+                ("delegate", ADDR1),
+                ("delegator", ADDR3),
+                ("weight", "2"),
+                ("tallies", r#"[["Cosmos Hub","0"],["Juno","2"]]"#),
+                ("turnout", "2"),
+            ])]
+        );
+        let ballot = BALLOTS
+            .load(deps.as_ref().storage, (Addr::unchecked(ADDR1), "some_id"))
+            .unwrap();
+        assert_eq!(ballot.weight, Uint128::new(2));
+
+        // Moving the global delegation elsewhere does the same for ADDR2
+        delegate(&mut deps, ADDR2, ADDR4, None);
+        assert_eq!(votes(&deps), (vec![0, 1], 1));
+
+        // The new delegate picks it up when they vote, and loses it when undelegated
+        vote(&mut deps, ADDR4, "Cosmos Hub");
+        assert_eq!(votes(&deps), (vec![2, 1], 3));
+        undelegate(&mut deps, ADDR2, None);
+        assert_eq!(votes(&deps), (vec![1, 1], 2));
+
+        // Re-delegating on the poll moves ADDR3's weight away from ADDR4
+        delegate(&mut deps, ADDR3, ADDR4, Some("some_id"));
+        vote(&mut deps, ADDR4, "Cosmos Hub");
+        assert_eq!(votes(&deps), (vec![2, 1], 3));
+        delegate(&mut deps, ADDR3, ADDR1, Some("some_id"));
+        assert_eq!(votes(&deps), (vec![1, 1], 2));
+
+        // While the poll is frozen, or the contract paused, delegations cannot move the tally
+        vote(&mut deps, ADDR1, "Juno");
+        assert_eq!(votes(&deps), (vec![1, 2], 3));
+        let msg = ExecuteMsg::FreezePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info(ADDR1, &[]), msg).unwrap();
+        let msg = ExecuteMsg::Undelegate {
+            poll_id: Some("some_id".to_string()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADDR3, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollFrozen {}));
+        assert_eq!(votes(&deps), (vec![1, 2], 3));
+        let msg = ExecuteMsg::UnfreezePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info(ADDR1, &[]), msg).unwrap();
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADDR1, &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+        let msg = ExecuteMsg::Delegate {
+            to: ADDR4.to_string(),
+            poll_id: Some("some_id".to_string()),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(ADDR3, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+        assert_eq!(votes(&deps), (vec![1, 2], 3));
     }
 
     #[test]
-    fn test_execute_vote_valid() {
+    fn test_query_all_polls() {
         let mut deps = mock_dependencies();
         let env = mock_env();
         let info = mock_info(ADDR1, &[]);
@@ -294,35 +4869,58 @@ mod tests {
         let msg = InstantiateMsg { admin: None };
         let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-        // Create the poll
+        // Create a poll
         let msg = ExecuteMsg::CreatePoll {
-            poll_id: "some_id".to_string(),
+            poll_id: Some("some_id_1".to_string()),
             question: "What's your favourite Cosmos coin?".to_string(),
             options: vec![
                 "Cosmos Hub".to_string(),
                 "Juno".to_string(),
                 "Osmosis".to_string(),
             ],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
         };
         let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-        // Create the vote, first time voting
-        let msg = ExecuteMsg::Vote {
-            poll_id: "some_id".to_string(),
-            vote: "Juno".to_string(),
+        // Create a second poll
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id_2".to_string()),
+            question: "What's your colour?".to_string(),
+            options: vec!["Red".to_string(), "Green".to_string(), "Blue".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
         };
-        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
-
-        // Change the vote
-        let msg = ExecuteMsg::Vote {
-            poll_id: "some_id".to_string(),
-            vote: "Osmosis".to_string(),
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        // Query
+        let msg = QueryMsg::AllPolls {
+            start_after: None,
+            limit: None,
+            order: None,
+            creator: None,
+            status: None,
         };
-        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let bin = query(deps.as_ref(), env, msg).unwrap();
+        let res: AllPollsResponse = from_binary(&bin).unwrap();
+        assert_eq!(res.polls.len(), 2);
     }
 
     #[test]
-    fn test_execute_vote_invalid() {
+    fn test_query_all_polls_paginated() {
         let mut deps = mock_dependencies();
         let env = mock_env();
         let info = mock_info(ADDR1, &[]);
@@ -330,37 +4928,85 @@ mod tests {
         let msg = InstantiateMsg { admin: None };
         let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-        // Create the vote, some_id poll is not created yet.
-        let msg = ExecuteMsg::Vote {
-            poll_id: "some_id".to_string(),
-            vote: "Juno".to_string(),
+        // ADDR1 creates three polls and ADDR2 creates one
+        for (poll_id, creator) in [("a", ADDR1), ("b", ADDR2), ("c", ADDR1), ("d", ADDR1)] {
+            let msg = ExecuteMsg::CreatePoll {
+                poll_id: Some(poll_id.to_string()),
+                question: "What's your favourite Cosmos coin?".to_string(),
+                options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+                start: None,
+                end: None,
+                voting_power: None,
+                kind: None,
+                voters: None,
+                quorum: None,
+                threshold: None,
+                msgs: None,
+                reveal_end: None,
+            };
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(creator, &[]), msg).unwrap();
+        }
+        // Close poll "d"
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "d".to_string(),
         };
-        // Unwrap to assert error
-        let _err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-        // Create the poll
-        let msg = ExecuteMsg::CreatePoll {
-            poll_id: "some_id".to_string(),
-            question: "What's your favourite Cosmos coin?".to_string(),
-            options: vec![
-                "Cosmos Hub".to_string(),
-                "Juno".to_string(),
-                "Osmosis".to_string(),
-            ],
+        // First page, ascending
+        let msg = QueryMsg::AllPolls {
+            start_after: None,
+            limit: Some(2),
+            order: None,
+            creator: None,
+            status: None,
         };
-        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: AllPollsResponse = from_binary(&bin).unwrap();
+        let ids: Vec<_> = res.polls.iter().map(|p| p.poll_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
 
-        // Vote on a now existing poll but the option "DVPN" does not exist
-        let msg = ExecuteMsg::Vote {
-            poll_id: "some_id".to_string(),
-            vote: "DVPN".to_string(),
+        // Second page, ascending
+        let msg = QueryMsg::AllPolls {
+            start_after: Some("b".to_string()),
+            limit: Some(2),
+            order: None,
+            creator: None,
+            status: None,
         };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: AllPollsResponse = from_binary(&bin).unwrap();
+        let ids: Vec<_> = res.polls.iter().map(|p| p.poll_id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "d"]);
 
-        let _err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        // Descending from "c"
+        let msg = QueryMsg::AllPolls {
+            start_after: Some("c".to_string()),
+            limit: None,
+            order: Some(OrderBy::Descending),
+            creator: None,
+            status: None,
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: AllPollsResponse = from_binary(&bin).unwrap();
+        let ids: Vec<_> = res.polls.iter().map(|p| p.poll_id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "a"]);
+
+        // Open polls from ADDR1
+        let msg = QueryMsg::AllPolls {
+            start_after: None,
+            limit: None,
+            order: None,
+            creator: Some(ADDR1.to_string()),
+            status: Some(PollStatus::Open),
+        };
+        let bin = query(deps.as_ref(), env, msg).unwrap();
+        let res: AllPollsResponse = from_binary(&bin).unwrap();
+        let ids: Vec<_> = res.polls.iter().map(|p| p.poll_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "c"]);
     }
 
     #[test]
-    fn test_query_all_polls() {
+    fn test_query_all_polls_scan_limit() {
         let mut deps = mock_dependencies();
         let env = mock_env();
         let info = mock_info(ADDR1, &[]);
@@ -368,30 +5014,101 @@ mod tests {
         let msg = InstantiateMsg { admin: None };
         let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-        // Create a poll
-        let msg = ExecuteMsg::CreatePoll {
-            poll_id: "some_id_1".to_string(),
-            question: "What's your favourite Cosmos coin?".to_string(),
-            options: vec![
-                "Cosmos Hub".to_string(),
-                "Juno".to_string(),
-                "Osmosis".to_string(),
-            ],
+        // More open polls than a single query will read
+        for i in 0..MAX_SCAN + 5 {
+            let msg = ExecuteMsg::CreatePoll {
+                poll_id: Some(format!("poll_{:03}", i)),
+                question: "What's your favourite Cosmos coin?".to_string(),
+                options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+                start: None,
+                end: None,
+                voting_power: None,
+                kind: None,
+                voters: None,
+                quorum: None,
+                threshold: None,
+                msgs: None,
+                reveal_end: None,
+            };
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        // Looking for closed polls stops after MAX_SCAN and says where it got to
+        let msg = QueryMsg::AllPolls {
+            start_after: None,
+            limit: None,
+            order: None,
+            creator: None,
+            status: Some(PollStatus::Closed),
         };
-        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: AllPollsResponse = from_binary(&bin).unwrap();
+        assert!(res.polls.is_empty());
+        let last_scanned = format!("poll_{:03}", MAX_SCAN - 1);
+        assert_eq!(res.last_scanned, Some(last_scanned.clone()));
 
-        // Create a second poll
-        let msg = ExecuteMsg::CreatePoll {
-            poll_id: "some_id_2".to_string(),
-            question: "What's your colour?".to_string(),
-            options: vec!["Red".to_string(), "Green".to_string(), "Blue".to_string()],
+        // Carrying on from there reaches the end
+        let msg = QueryMsg::AllPolls {
+            start_after: Some(last_scanned),
+            limit: None,
+            order: None,
+            creator: None,
+            status: Some(PollStatus::Closed),
         };
-        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
-        // Query
-        let msg = QueryMsg::AllPolls {};
         let bin = query(deps.as_ref(), env, msg).unwrap();
         let res: AllPollsResponse = from_binary(&bin).unwrap();
-        assert_eq!(res.polls.len(), 2);
+        assert!(res.polls.is_empty());
+        assert_eq!(res.last_scanned, None);
+    }
+
+    #[test]
+    fn test_query_polls_by_creator() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // ADDR1 creates two polls and ADDR2 creates one
+        for (poll_id, creator) in [("a", ADDR1), ("b", ADDR2), ("c", ADDR1)] {
+            let msg = ExecuteMsg::CreatePoll {
+                poll_id: Some(poll_id.to_string()),
+                question: "What's your favourite Cosmos coin?".to_string(),
+                options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+                start: None,
+                end: None,
+                voting_power: None,
+                kind: None,
+                voters: None,
+                quorum: None,
+                threshold: None,
+                msgs: None,
+                reveal_end: None,
+            };
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(creator, &[]), msg).unwrap();
+        }
+
+        let msg = QueryMsg::PollsByCreator {
+            creator: ADDR1.to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: AllPollsResponse = from_binary(&bin).unwrap();
+        let ids: Vec<_> = res.polls.iter().map(|p| p.poll_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "c"]);
+
+        // Next page
+        let msg = QueryMsg::PollsByCreator {
+            creator: ADDR1.to_string(),
+            start_after: Some("a".to_string()),
+            limit: None,
+        };
+        let bin = query(deps.as_ref(), env, msg).unwrap();
+        let res: AllPollsResponse = from_binary(&bin).unwrap();
+        let ids: Vec<_> = res.polls.iter().map(|p| p.poll_id.as_str()).collect();
+        assert_eq!(ids, vec!["c"]);
     }
 
     #[test]
@@ -405,13 +5122,22 @@ mod tests {
 
         // Create a poll
         let msg = ExecuteMsg::CreatePoll {
-            poll_id: "some_id_1".to_string(),
+            poll_id: Some("some_id_1".to_string()),
             question: "What's your favourite Cosmos coin?".to_string(),
             options: vec![
                 "Cosmos Hub".to_string(),
                 "Juno".to_string(),
                 "Osmosis".to_string(),
             ],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
         };
         let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
@@ -445,20 +5171,29 @@ mod tests {
 
         // Create a poll
         let msg = ExecuteMsg::CreatePoll {
-            poll_id: "some_id_1".to_string(),
+            poll_id: Some("some_id_1".to_string()),
             question: "What's your favourite Cosmos coin?".to_string(),
             options: vec![
                 "Cosmos Hub".to_string(),
                 "Juno".to_string(),
                 "Osmosis".to_string(),
             ],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
         };
         let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
         // Create a vote
         let msg = ExecuteMsg::Vote {
             poll_id: "some_id_1".to_string(),
-            vote: "Juno".to_string(),
+            vote: Choice::Single("Juno".to_string()),
         };
         let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
@@ -482,4 +5217,300 @@ mod tests {
         // Expect the vote to not exist
         assert!(res.vote.is_none());
     }
+
+    #[test]
+    fn test_migrate_legacy_polls() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        // Pretend v0.1.0 is deployed with a poll in the old tuple format
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1.0").unwrap();
+        let legacy = LegacyPoll {
+            creator: Addr::unchecked(ADDR1),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec![("Cosmos Hub".to_string(), 2), ("Juno".to_string(), 5)],
+            start: None,
+            end: None,
+        };
+        LEGACY_POLLS
+            .save(deps.as_mut().storage, "some_id", &legacy)
+            .unwrap();
+        let ballot = Ballot {
+            option: Choice::Single("Juno".to_string()),
+            weight: Uint128::new(1),
+            withdrawn: false,
+        };
+        BALLOTS
+            .save(
+                deps.as_mut().storage,
+                (Addr::unchecked(ADDR2), "some_id"),
+                &ballot,
+            )
+            .unwrap();
+
+        let res = migrate(deps.as_mut(), env, MigrateMsg {}).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "migrate"),
+                attr("from_version", "0.1.0"),
+                attr("to_version", CONTRACT_VERSION)
+            ]
+        );
+
+        // The poll survived with its votes intact
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        assert_eq!(
+            poll.options,
+            vec![
+                PollOption {
+                    text: "Cosmos Hub".to_string(),
+                    votes: Uint128::new(2),
+                    msgs: vec![],
+                },
+                PollOption {
+                    text: "Juno".to_string(),
+                    votes: Uint128::new(5),
+                    msgs: vec![],
+                },
+            ]
+        );
+        // It is now indexed by its creator
+        let by_creator = polls()
+            .idx
+            .creator
+            .prefix(Addr::unchecked(ADDR1))
+            .keys(deps.as_ref().storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(by_creator, vec!["some_id".to_string()]);
+        // And its ballots are indexed by poll
+        let indexed = POLL_BALLOTS
+            .load(deps.as_ref().storage, ("some_id", Addr::unchecked(ADDR2)))
+            .unwrap();
+        assert_eq!(indexed, ballot);
+        // And counted towards the turnout and ballot count
+        let poll = polls().load(deps.as_ref().storage, "some_id").unwrap();
+        assert_eq!(poll.turnout, Uint128::new(1));
+        assert_eq!(poll.ballot_count, 1);
+        let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_seeds_poll_count() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Pretend v0.5.0 is deployed with polls named by their clients
+        for poll_id in ["00000000000000000007", "42", "some_id"] {
+            let msg = ExecuteMsg::CreatePoll {
+                poll_id: Some(poll_id.to_string()),
+                question: "What's your favourite Cosmos coin?".to_string(),
+                options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+                start: None,
+                end: None,
+                voting_power: None,
+                kind: None,
+                voters: None,
+                quorum: None,
+                threshold: None,
+                msgs: None,
+                reveal_end: None,
+            };
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        POLL_COUNT.remove(deps.as_mut().storage);
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.5.0").unwrap();
+
+        // The sequence carries on after the highest ID in its own form, client IDs stay put
+        let _res = migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+        let count = POLL_COUNT.load(deps.as_ref().storage).unwrap();
+        assert_eq!(count, 7);
+        assert!(polls().has(deps.as_ref().storage, "42"));
+
+        // A sequence that has run out fails cleanly rather than overflowing
+        POLL_COUNT.save(deps.as_mut().storage, &u64::MAX).unwrap();
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: None,
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+    }
+
+    #[test]
+    fn test_migrate_invalid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        // A different contract cannot be migrated into this one
+        cw2::set_contract_version(deps.as_mut().storage, "crates.io:cw20-base", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidContract { .. }));
+
+        // Nor can we go back to an older version
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), env, MigrateMsg {}).unwrap_err();
+        assert!(matches!(err, ContractError::CannotDowngrade { .. }));
+    }
+
+    #[test]
+    fn test_query_votes_by_voter() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Create three polls, ADDR1 votes on all of them and ADDR2 on one
+        for poll_id in ["a", "b", "c"] {
+            let msg = ExecuteMsg::CreatePoll {
+                poll_id: Some(poll_id.to_string()),
+                question: "What's your favourite Cosmos coin?".to_string(),
+                options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+                start: None,
+                end: None,
+                voting_power: None,
+                kind: None,
+                voters: None,
+                quorum: None,
+                threshold: None,
+                msgs: None,
+                reveal_end: None,
+            };
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let msg = ExecuteMsg::Vote {
+                poll_id: poll_id.to_string(),
+                vote: Choice::Single("Juno".to_string()),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let msg = ExecuteMsg::Vote {
+            poll_id: "b".to_string(),
+            vote: Choice::Single("Cosmos Hub".to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+
+        // First page of ADDR1's votes
+        let msg = QueryMsg::VotesByVoter {
+            address: ADDR1.to_string(),
+            start_after: None,
+            limit: Some(2),
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: VotesResponse = from_binary(&bin).unwrap();
+        let ids: Vec<_> = res.votes.iter().map(|v| v.poll_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert_eq!(
+            res.votes[0].ballot.option,
+            Choice::Single("Juno".to_string())
+        );
+
+        // Second page
+        let msg = QueryMsg::VotesByVoter {
+            address: ADDR1.to_string(),
+            start_after: Some("b".to_string()),
+            limit: Some(2),
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: VotesResponse = from_binary(&bin).unwrap();
+        let ids: Vec<_> = res.votes.iter().map(|v| v.poll_id.as_str()).collect();
+        assert_eq!(ids, vec!["c"]);
+
+        // ADDR2 only voted once
+        let msg = QueryMsg::VotesByVoter {
+            address: ADDR2.to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let bin = query(deps.as_ref(), env, msg).unwrap();
+        let res: VotesResponse = from_binary(&bin).unwrap();
+        assert_eq!(res.votes.len(), 1);
+        assert_eq!(
+            res.votes[0].ballot.option,
+            Choice::Single("Cosmos Hub".to_string())
+        );
+    }
+
+    #[test]
+    fn test_query_poll_voters() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg { admin: None };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Create a poll which all three addresses vote on
+        let msg = ExecuteMsg::CreatePoll {
+            poll_id: Some("some_id".to_string()),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Cosmos Hub".to_string(), "Juno".to_string()],
+            start: None,
+            end: None,
+            voting_power: None,
+            kind: None,
+            voters: None,
+            quorum: None,
+            threshold: None,
+            msgs: None,
+            reveal_end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        for voter in [ADDR1, ADDR2, ADDR3] {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "some_id".to_string(),
+                vote: Choice::Single("Juno".to_string()),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+        // ADDR2 changes their mind
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            vote: Choice::Single("Cosmos Hub".to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+
+        // First page
+        let msg = QueryMsg::PollVoters {
+            poll_id: "some_id".to_string(),
+            start_after: None,
+            limit: Some(2),
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: VotesResponse = from_binary(&bin).unwrap();
+        let voters: Vec<_> = res.votes.iter().map(|v| v.voter.as_str()).collect();
+        assert_eq!(voters, vec![ADDR1, ADDR2]);
+        assert_eq!(
+            res.votes[1].ballot.option,
+            Choice::Single("Cosmos Hub".to_string())
+        );
+
+        // Second page
+        let msg = QueryMsg::PollVoters {
+            poll_id: "some_id".to_string(),
+            start_after: Some(ADDR2.to_string()),
+            limit: Some(2),
+        };
+        let bin = query(deps.as_ref(), env, msg).unwrap();
+        let res: VotesResponse = from_binary(&bin).unwrap();
+        let voters: Vec<_> = res.votes.iter().map(|v| v.voter.as_str()).collect();
+        assert_eq!(voters, vec![ADDR3]);
+    }
 }
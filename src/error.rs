@@ -1,4 +1,5 @@
 use cosmwasm_std::StdError;
+use cw_utils::PaymentError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +7,12 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Semver(#[from] semver::Error),
+
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
 
@@ -20,4 +27,136 @@ pub enum ContractError {
 
     #[error("PollNotExisted")]
     PollNotExisted,
+
+    #[error("Poll has not started yet")]
+    PollNotStarted {},
+
+    #[error("Poll is already closed")]
+    PollClosed {},
+
+    #[error("Poll must end after it starts")]
+    InvalidPollPeriod {},
+
+    #[error("Cannot migrate from a different contract: {contract}")]
+    InvalidContract { contract: String },
+
+    #[error("Cannot migrate from newer version {stored} to {current}")]
+    CannotDowngrade { stored: String, current: String },
+
+    #[error("No voting power for this poll")]
+    NoVotingPower {},
+
+    #[error("Token is not accepted by this poll")]
+    InvalidToken {},
+
+    #[error("Poll is still open")]
+    PollNotClosed {},
+
+    #[error("Nothing to withdraw")]
+    NothingToWithdraw {},
+
+    #[error("Invalid choice for this kind of poll")]
+    InvalidChoice {},
+
+    #[error("Too many choices")]
+    TooManyChoices {},
+
+    #[error("The same option was chosen twice")]
+    DuplicateChoice {},
+
+    #[error("Max choices must be between 1 and the number of options")]
+    InvalidMaxChoices {},
+
+    #[error("Quadratic polls need a credit budget")]
+    InvalidCredits {},
+
+    #[error("Votes cost {cost} credits but only {credits} are available")]
+    InsufficientCredits { cost: u128, credits: u64 },
+
+    #[error("Not eligible to vote on this poll")]
+    NotEligible {},
+
+    #[error("Poll does not have an allowlist")]
+    PollNotAllowlisted {},
+
+    #[error("Percentages must not be above 1")]
+    InvalidPercentage {},
+
+    #[error("A quorum percentage needs a poll with a known eligible weight")]
+    UnknownEligibleWeight {},
+
+    #[error("There must be one list of messages per option")]
+    InvalidMsgs {},
+
+    #[error("Poll did not pass")]
+    PollNotPassed {},
+
+    #[error("Poll has already been executed")]
+    AlreadyExecuted {},
+
+    #[error("Poll is not in its reveal period")]
+    NotRevealPeriod {},
+
+    #[error("No sealed vote to reveal")]
+    NothingToReveal {},
+
+    #[error("Vote and salt do not match the commitment")]
+    InvalidReveal {},
+
+    #[error("Cannot delegate to yourself")]
+    SelfDelegation {},
+
+    #[error("No delegation to remove")]
+    NotDelegated {},
+
+    #[error("Delegate already has the maximum number of delegators")]
+    TooManyDelegators {},
+
+    #[error("Poll is not ranked choice")]
+    NotRanked {},
+
+    #[error("Ranked poll has to be counted with TallyRanked first")]
+    NotTallied {},
+
+    #[error("Ranked poll has already been counted")]
+    AlreadyTallied {},
+
+    #[error("Question cannot be empty")]
+    EmptyQuestion {},
+
+    #[error("Options cannot be empty")]
+    EmptyOption {},
+
+    #[error("A poll needs at least {min} options")]
+    TooFewOptions { min: u32 },
+
+    #[error("Option {option} is listed more than once")]
+    DuplicateOption { option: String },
+
+    #[error("Poll ID may only contain letters, digits, '-' and '_'")]
+    InvalidPollId {},
+
+    #[error("{field} is longer than {max} characters")]
+    TooLong { field: String, max: u32 },
+
+    #[error("Invalid poll limits")]
+    InvalidLimits {},
+
+    #[error("Creating a poll requires a deposit of {expected}")]
+    InvalidDeposit { expected: String },
+
+    #[error("Poll has no deposit held")]
+    NoDeposit {},
+
+    #[error("Poll did not reach quorum")]
+    QuorumNotReached {},
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Poll is frozen")]
+    PollFrozen {},
+
+    #[error("Poll cannot be changed once it has votes")]
+    PollHasVotes {},
 }
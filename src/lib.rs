@@ -1,6 +1,8 @@
 pub mod contract;
 mod error;
+mod migrations;
 pub mod msg;
 pub mod state;
+mod validation;
 
 pub use crate::error::ContractError;
@@ -0,0 +1,213 @@
+// State migrations run by the `migrate` entry point, one module per contract
+// version that changed the storage layout.
+
+pub mod v0_2 {
+    use serde::{Deserialize, Serialize};
+
+    use cosmwasm_std::{Addr, Order, StdResult, Storage, Uint128};
+    use cw_storage_plus::Map;
+    use cw_utils::{Expiration, Scheduled};
+
+    use crate::state::{Poll, PollKind, PollOption, VotingPower};
+
+    // A poll as stored up to v0.1.x, options were (text, votes) tuples
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct LegacyPoll {
+        pub creator: Addr,
+        pub question: String,
+        pub options: Vec<(String, u64)>,
+        pub start: Option<Scheduled>,
+        pub end: Option<Expiration>,
+    }
+
+    // Same namespace as polls() so we read the records in their old format
+    pub const LEGACY_POLLS: Map<&str, LegacyPoll> = Map::new("polls");
+
+    // The records are rewritten without touching the indexes, the IndexedMap
+    // would try to read the old value in the new format to update them
+    const POLLS: Map<&str, Poll> = Map::new("polls");
+
+    pub fn migrate_poll_options(storage: &mut dyn Storage) -> StdResult<()> {
+        let legacy_polls = LEGACY_POLLS
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for (poll_id, legacy) in legacy_polls {
+            let poll = Poll {
+                creator: legacy.creator,
+                question: legacy.question,
+                options: legacy
+                    .options
+                    .into_iter()
+                    .map(|(text, votes)| PollOption {
+                        text,
+                        votes: Uint128::from(votes),
+                        msgs: vec![],
+                    })
+                    .collect(),
+                start: legacy.start,
+                end: legacy.end,
+                voting_power: VotingPower::Equal,
+                kind: PollKind::SingleChoice,
+                allowlisted: false,
+                created_height: 0,
+                quorum: None,
+                threshold: None,
+                turnout: Uint128::zero(),
+                executed: false,
+                reveal_end: None,
+                deposit: None,
+                frozen: false,
+                ballot_count: 0,
+                group_weight: None,
+            };
+            POLLS.save(storage, &poll_id, &poll)?;
+        }
+        Ok(())
+    }
+}
+
+pub mod v0_3 {
+    use cosmwasm_std::{Order, StdResult, Storage};
+
+    use crate::state::polls;
+
+    // Saving every poll through the IndexedMap writes its creator index entry
+    pub fn index_polls_by_creator(storage: &mut dyn Storage) -> StdResult<()> {
+        let existing = polls()
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for (poll_id, poll) in existing {
+            polls().save(storage, &poll_id, &poll)?;
+        }
+        Ok(())
+    }
+}
+
+pub mod v0_4 {
+    use cosmwasm_std::{Order, StdResult, Storage};
+
+    use crate::state::{BALLOTS, POLL_BALLOTS};
+
+    // Backfill the per poll ballot index from the existing ballots
+    pub fn index_ballots_by_poll(storage: &mut dyn Storage) -> StdResult<()> {
+        let ballots = BALLOTS
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for ((voter, poll_id), ballot) in ballots {
+            POLL_BALLOTS.save(storage, (&poll_id, voter), &ballot)?;
+        }
+        Ok(())
+    }
+}
+
+pub mod v0_5 {
+    use cosmwasm_std::{Order, StdResult, Storage, Uint128};
+
+    use crate::state::{polls, POLL_BALLOTS};
+
+    // Work out the turnout of existing polls from their ballots
+    pub fn count_turnout(storage: &mut dyn Storage) -> StdResult<()> {
+        let poll_ids = polls()
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for poll_id in poll_ids {
+            let turnout = POLL_BALLOTS
+                .prefix(&poll_id)
+                .range(storage, None, None, Order::Ascending)
+                .try_fold(Uint128::zero(), |turnout, item| {
+                    item.map(|(_, ballot)| turnout + ballot.weight)
+                })?;
+            let mut poll = polls().load(storage, &poll_id)?;
+            poll.turnout = turnout;
+            polls().save(storage, &poll_id, &poll)?;
+        }
+        Ok(())
+    }
+}
+
+pub mod v0_6 {
+    use cosmwasm_std::{Order, StdResult, Storage};
+
+    use crate::state::{polls, sequence_poll_id, POLL_COUNT};
+
+    // Start the ID sequence after the highest ID clients already chose in the zero padded
+    // form it hands out. Other IDs, numeric or not, can never clash with it
+    pub fn seed_poll_count(storage: &mut dyn Storage) -> StdResult<()> {
+        let count = polls()
+            .keys(storage, None, None, Order::Ascending)
+            .filter_map(|poll_id| poll_id.map(|id| sequence_number(&id)).transpose())
+            .try_fold(0, |count, id| id.map(|id| count.max(id)))?;
+        POLL_COUNT.save(storage, &count)
+    }
+
+    fn sequence_number(poll_id: &str) -> Option<u64> {
+        poll_id
+            .parse::<u64>()
+            .ok()
+            .filter(|count| sequence_poll_id(*count) == poll_id)
+    }
+}
+
+pub mod v0_7 {
+    use cosmwasm_std::{Order, StdResult, Storage};
+
+    use crate::state::{polls, POLL_BALLOTS};
+
+    // Count the ballots already cast so polls with votes cannot be edited
+    pub fn count_ballots(storage: &mut dyn Storage) -> StdResult<()> {
+        let poll_ids = polls()
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for poll_id in poll_ids {
+            let ballot_count = POLL_BALLOTS
+                .prefix(&poll_id)
+                .keys(storage, None, None, Order::Ascending)
+                .count() as u64;
+            let mut poll = polls().load(storage, &poll_id)?;
+            poll.ballot_count = ballot_count;
+            polls().save(storage, &poll_id, &poll)?;
+        }
+        Ok(())
+    }
+}
+
+pub mod v0_8 {
+    use cosmwasm_std::{Order, StdResult, Storage};
+
+    use crate::state::{DELEGATED_VOTES, DELEGATOR_VOTES};
+
+    // Backfill the per delegator index of delegated votes
+    pub fn index_delegated_votes(storage: &mut dyn Storage) -> StdResult<()> {
+        let delegated = DELEGATED_VOTES
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for (poll_id, delegator) in delegated {
+            DELEGATOR_VOTES.save(storage, (delegator, &poll_id), &())?;
+        }
+        Ok(())
+    }
+}
+
+pub mod v0_9 {
+    use cosmwasm_std::{Order, StdResult, Storage};
+
+    use crate::state::{DELEGATED_VOTES, DELEGATE_VOTES};
+
+    // Backfill the per delegate index of delegated votes
+    pub fn index_votes_by_delegate(storage: &mut dyn Storage) -> StdResult<()> {
+        let delegated = DELEGATED_VOTES
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for ((poll_id, delegator), delegated) in delegated {
+            DELEGATE_VOTES.save(storage, (&poll_id, delegated.delegate, delegator), &())?;
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,13 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::state::{Ballot, Poll};
+use cosmwasm_std::{Addr, Coin, CosmosMsg, Order, Uint128};
+use cw20::Cw20ReceiveMsg;
+use cw_utils::{Expiration, Scheduled};
+
+use crate::state::{
+    Ballot, Choice, Poll, PollKind, PollLimits, PollOption, PollStatus, Requirement, VotingPower,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -13,22 +19,188 @@ pub struct InstantiateMsg {
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     CreatePoll {
-        poll_id: String,
+        // Assigned from a zero padded sequence when left out
+        poll_id: Option<String>,
         question: String,
         options: Vec<String>,
+        start: Option<Scheduled>,
+        end: Option<Expiration>,
+        voting_power: Option<VotingPower>,
+        kind: Option<PollKind>,
+        // Restricts voting to these addresses when set
+        voters: Option<Vec<String>>,
+        quorum: Option<Requirement>,
+        threshold: Option<Requirement>,
+        // Messages to dispatch if the option at the same position wins, admin only
+        msgs: Option<Vec<Vec<CosmosMsg>>>,
+        // Makes the poll secret, sealed votes can be revealed from `end` until this
+        reveal_end: Option<Expiration>,
     },
     Vote {
         poll_id: String,
-        vote: String,
+        vote: Choice,
+    },
+    // Creator only, before anyone has voted
+    UpdatePoll {
+        poll_id: String,
+        question: Option<String>,
+        options: Option<Vec<String>>,
+    },
+    Reveal {
+        poll_id: String,
+        vote: Choice,
+        salt: String,
+    },
+    ClosePoll {
+        poll_id: String,
+    },
+    DeletePoll {
+        poll_id: String,
+    },
+    // Count up to `limit` ballots of a closed ranked poll's instant-runoff. Call it until it
+    // reports finished, Execute and ClaimDeposit need the finished count
+    TallyRanked {
+        poll_id: String,
+        limit: Option<u32>,
+    },
+    // Dispatch the winning option's messages once the poll has passed
+    Execute {
+        poll_id: String,
+    },
+    Receive(Cw20ReceiveMsg),
+    Withdraw {
+        poll_id: String,
+    },
+    AddVoters {
+        poll_id: String,
+        voters: Vec<String>,
+    },
+    RemoveVoters {
+        poll_id: String,
+        voters: Vec<String>,
+    },
+    // Hand voting power to another address on every poll, or only on `poll_id`.
+    // It is counted when the delegate votes, unless the delegator votes first.
+    // A delegate takes at most 50 delegators in each scope
+    Delegate {
+        to: String,
+        poll_id: Option<String>,
+    },
+    Undelegate {
+        poll_id: Option<String>,
+    },
+    // Admin only
+    UpdateLimits {
+        limits: PollLimits,
+    },
+    // Admin only, None or a zero amount makes creating polls free
+    UpdateDeposit {
+        deposit: Option<Coin>,
+    },
+    // Refund the creation deposit once the poll has ended with quorum and at least one vote
+    ClaimDeposit {
+        poll_id: String,
+    },
+    // Admin only, the creation deposit goes to the admin
+    FlagSpam {
+        poll_id: String,
+    },
+    // Admin handover in two steps so it cannot go to an address nobody controls
+    ProposeNewAdmin {
+        new_admin: String,
+    },
+    AcceptAdmin {},
+    // Leaves the contract without an admin for good
+    RenounceAdmin {},
+    // Admin only, stops polls being created and votes cast on every poll. Reveals still go through
+    Pause {},
+    Unpause {},
+    // Admin only, stops votes on a single poll but not reveals
+    FreezePoll {
+        poll_id: String,
+    },
+    UnfreezePoll {
+        poll_id: String,
     },
 }
 
+// Hook messages sent along with CW20 tokens
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    Vote { poll_id: String, vote: Choice },
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    AllPolls {},
-    Poll { poll_id: String },
-    Vote { poll_id: String, address: String },
+    Config {},
+    AllPolls {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order: Option<OrderBy>,
+        creator: Option<String>,
+        status: Option<PollStatus>,
+    },
+    PollsByCreator {
+        creator: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    Poll {
+        poll_id: String,
+    },
+    Vote {
+        poll_id: String,
+        address: String,
+    },
+    VotesByVoter {
+        address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    PollVoters {
+        poll_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    RankedResult {
+        poll_id: String,
+    },
+    IsEligible {
+        poll_id: String,
+        address: String,
+    },
+    PollResult {
+        poll_id: String,
+    },
+    // The delegate of an address, for a poll if given
+    Delegation {
+        address: String,
+        poll_id: Option<String>,
+    },
+    // Sealed votes on a secret poll that have not been revealed
+    UnrevealedVotes {
+        poll_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderBy {
+    Ascending,
+    Descending,
+}
+
+impl From<OrderBy> for Order {
+    fn from(order: OrderBy) -> Order {
+        match order {
+            OrderBy::Ascending => Order::Ascending,
+            OrderBy::Descending => Order::Descending,
+        }
+    }
 }
 
 // #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -39,19 +211,103 @@ pub enum QueryMsg {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ConfigResponse {
+    pub admin: Option<Addr>,
+    pub pending_admin: Option<Addr>,
+    pub limits: PollLimits,
+    pub deposit: Option<Coin>,
+    pub paused: bool,
+}
+
+// Returned as the data of CreatePoll
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CreatePollResponse {
+    pub poll_id: String,
+}
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct AllPollsResponse {
-    pub polls: Vec<Poll>,
+    pub polls: Vec<PollInfo>,
+    // Set when the scan limit was hit before the page filled, pass it as start_after to continue
+    pub last_scanned: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PollInfo {
+    pub poll_id: String,
+    pub poll: Poll,
+    pub status: PollStatus,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct PollResponse {
     pub poll: Option<Poll>,
+    pub status: Option<PollStatus>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct VoteResponse {
     pub vote: Option<Ballot>,
 }
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BallotInfo {
+    pub poll_id: String,
+    pub voter: Addr,
+    pub ballot: Ballot,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct VotesResponse {
+    pub votes: Vec<BallotInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RankedRound {
+    // First preference counts among the options still in the running
+    pub tallies: Vec<PollOption>,
+    // None on the final round
+    pub eliminated: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RankedResultResponse {
+    pub rounds: Vec<RankedRound>,
+    pub winner: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct EligibleResponse {
+    pub eligible: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PollOutcome {
+    // The poll is still running
+    Pending,
+    Passed,
+    Rejected,
+    NoQuorum,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PollResultResponse {
+    pub outcome: PollOutcome,
+    pub winner: Option<String>,
+    pub winning_votes: Uint128,
+    // Lead of the winning option over the runner up
+    pub margin: Uint128,
+    pub total_votes: Uint128,
+    pub turnout: Uint128,
+    // None when the poll has no fixed set of voters
+    pub eligible_weight: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DelegationResponse {
+    pub delegate: Option<Addr>,
+}
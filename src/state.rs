@@ -1,31 +1,276 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Addr;
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Binary, BlockInfo, Coin, CosmosMsg, Decimal, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_utils::{Expiration, Scheduled};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
-    pub admin: Addr, // allow the admin to delete polls
+    pub admin: Option<Addr>, // allow the admin to delete polls, None once renounced
+    #[serde(default)]
+    pub limits: PollLimits,
+    // Native funds that must be sent with CreatePoll
+    #[serde(default)]
+    pub deposit: Option<Coin>,
+    // Stops polls being created and votes cast while set
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl Config {
+    pub fn is_admin(&self, addr: &Addr) -> bool {
+        self.admin.as_ref() == Some(addr)
+    }
+}
+
+// Bounds on what a poll can be created with, set by the admin
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PollLimits {
+    pub min_options: u32,
+    pub max_options: u32,
+    pub max_id_length: u32,
+    pub max_question_length: u32,
+    pub max_option_length: u32,
+}
+
+impl Default for PollLimits {
+    fn default() -> Self {
+        PollLimits {
+            min_options: 2,
+            max_options: 10,
+            max_id_length: 64,
+            max_question_length: 256,
+            max_option_length: 128,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Poll {
     pub creator: Addr,
     pub question: String,
-    pub options: Vec<(String, u64)>,
+    pub options: Vec<PollOption>,
+    pub start: Option<Scheduled>, // None means the poll opens straight away
+    pub end: Option<Expiration>,  // None means the poll never closes
+    #[serde(default)]
+    pub voting_power: VotingPower,
+    #[serde(default)]
+    pub kind: PollKind,
+    // Only addresses in ALLOWLIST may vote when set
+    #[serde(default)]
+    pub allowlisted: bool,
+    // Snapshot height for voting power read from other contracts
+    #[serde(default)]
+    pub created_height: u64,
+    // Minimum turnout for the result to count
+    #[serde(default)]
+    pub quorum: Option<Requirement>,
+    // Minimum support the winning option needs to pass
+    #[serde(default)]
+    pub threshold: Option<Requirement>,
+    // Total weight of the ballots cast
+    #[serde(default)]
+    pub turnout: Uint128,
+    // Set once the winning option's messages have been dispatched
+    #[serde(default)]
+    pub executed: bool,
+    // Makes the poll secret, votes are sealed until `end` and revealed until this
+    #[serde(default)]
+    pub reveal_end: Option<Expiration>,
+    // The creation deposit still held by the contract
+    #[serde(default)]
+    pub deposit: Option<Coin>,
+    // Stops votes on this poll while set and the contract has an admin
+    #[serde(default)]
+    pub frozen: bool,
+    // The number of addresses that have voted, the poll can be edited while it is 0
+    #[serde(default)]
+    pub ballot_count: u64,
+    // The cw4 group's total weight when the poll was created, quorum percentages use it
+    #[serde(default)]
+    pub group_weight: Option<Uint128>,
 }
 
+// A minimum amount of voting power, either outright or as a share
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Ballot {
+#[serde(rename_all = "snake_case")]
+pub enum Requirement {
+    Absolute(Uint128),
+    // Quorum is a share of the eligible weight, threshold a share of the turnout
+    Percentage(Decimal),
+}
+
+// How voters pick among the options
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PollKind {
+    // One option per ballot
+    SingleChoice,
+    // Approve of up to `max_choices` options, each one gets the full weight
+    Approval { max_choices: u32 },
+    // Rank the options in order of preference, decided by instant-runoff
+    Ranked,
+    // Spread votes over the options, n votes on an option cost n² of the voter's credits
+    Quadratic { credits: u64 },
+}
+
+impl Default for PollKind {
+    fn default() -> Self {
+        PollKind::SingleChoice
+    }
+}
+
+// What a voter picked, a plain string for a single option, a list, or votes per option
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum Choice {
+    Single(String),
+    Multiple(Vec<String>),
+    Allocations(Vec<Allocation>),
+    // Cast on secret polls, the SHA-256 of the poll ID, the voter's address, the vote's JSON
+    // and a salt concatenated in that order, e.g. sha256("some_id" + "juno1..." + "\"Yes\"" + salt)
+    Sealed { commitment: Binary },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Allocation {
     pub option: String,
+    pub votes: u64,
+}
+
+// How much a single ballot counts for in the tally
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VotingPower {
+    // One vote per address
+    Equal,
+    // The amount of `denom` sent along with the vote, the funds stay locked in the contract
+    NativeToken { denom: String },
+    // The amount of CW20 tokens sent to the contract with a vote hook, also locked
+    Cw20Token { address: Addr },
+    // The member's weight in a cw4 group at the height the poll was created
+    Cw4Group { address: Addr },
+}
+
+impl Default for VotingPower {
+    fn default() -> Self {
+        VotingPower::Equal
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PollOption {
+    pub text: String,
+    pub votes: Uint128,
+    // Dispatched by `Execute` when this option wins
+    #[serde(default)]
+    pub msgs: Vec<CosmosMsg>,
+}
+
+impl PollOption {
+    pub fn new(text: String) -> Self {
+        PollOption {
+            text,
+            votes: Uint128::zero(),
+            msgs: vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PollStatus {
+    Pending,
+    Open,
+    Closed,
+    // Voting has ended on a secret poll and sealed votes can be revealed
+    Revealing,
+    // Closed and the winning option's messages have been dispatched
+    Executed,
+}
+
+impl Poll {
+    // Work out where the poll is in its lifecycle for the given block
+    pub fn status(&self, block: &BlockInfo) -> PollStatus {
+        if self.executed {
+            return PollStatus::Executed;
+        }
+        if let Some(end) = self.end {
+            if end.is_expired(block) {
+                return match self.reveal_end {
+                    Some(reveal_end) if !reveal_end.is_expired(block) => PollStatus::Revealing,
+                    _ => PollStatus::Closed,
+                };
+            }
+        }
+        match self.start {
+            Some(start) if !start.is_triggered(block) => PollStatus::Pending,
+            _ => PollStatus::Open,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Ballot {
+    pub option: Choice,
+    // Ballots cast before weighted voting existed counted once
+    #[serde(default = "default_weight")]
+    pub weight: Uint128,
+    // Set once the locked tokens have been handed back
+    #[serde(default)]
+    pub withdrawn: bool,
+}
+
+fn default_weight() -> Uint128 {
+    Uint128::new(1)
+}
+
+// Voting power a delegator handed over that was counted in their delegate's ballot
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DelegatedVote {
+    pub delegate: Addr,
+    pub weight: Uint128,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
-// A map with a String key and Poll value
+// An admin proposed by the current one, who takes over once they accept
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
+
+// The last number handed out as a poll ID
+pub const POLL_COUNT: Item<u64> = Item::new("poll_count");
+
+// IDs from the sequence are zero padded to the width of a u64 so they sort
+// numerically next to the string IDs clients choose
+pub fn sequence_poll_id(count: u64) -> String {
+    format!("{:020}", count)
+}
+
+pub struct PollIndexes<'a> {
+    // Lets us list the polls of a creator without scanning every poll
+    pub creator: MultiIndex<'a, Addr, Poll, String>,
+}
+
+impl<'a> IndexList<Poll> for PollIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Poll>> + '_> {
+        let v: Vec<&dyn Index<Poll>> = vec![&self.creator];
+        Box::new(v.into_iter())
+    }
+}
+
+// An indexed map with a String key and Poll value
 // The key must be unique, this could be a UUID or a generated slug
-pub const POLLS: Map<&str, Poll> = Map::new("polls");
+pub fn polls<'a>() -> IndexedMap<'a, &'a str, Poll, PollIndexes<'a>> {
+    let indexes = PollIndexes {
+        creator: MultiIndex::new(
+            |poll: &Poll| poll.creator.clone(),
+            "polls",
+            "polls__creator",
+        ),
+    };
+    IndexedMap::new("polls", indexes)
+}
 
 // A map with a tuple key (Addr, String) and a Ballot value
 // The tuple is made up of the voter's address and the polls ID
@@ -33,3 +278,45 @@ pub const POLLS: Map<&str, Poll> = Map::new("polls");
 // A key of ("wasm1xxx", "1") will point to the vote of address
 // wasm1xxx for poll 1
 pub const BALLOTS: Map<(Addr, &str), Ballot> = Map::new("ballots");
+
+// The same ballots keyed the other way round, (poll ID, voter's address),
+// so every ballot cast in a poll can be listed. Must be kept in sync with BALLOTS
+pub const POLL_BALLOTS: Map<(&str, Addr), Ballot> = Map::new("poll_ballots");
+
+// The addresses allowed to vote on allowlisted polls, keyed (poll ID, voter's address)
+pub const ALLOWLIST: Map<(&str, Addr), ()> = Map::new("allowlist");
+
+// Who each address hands its voting power to on every poll, and the same
+// keyed (delegate, delegator) so a delegate can find its delegators
+pub const DELEGATIONS: Map<Addr, Addr> = Map::new("delegations");
+pub const DELEGATORS: Map<(Addr, Addr), ()> = Map::new("delegators");
+
+// Delegations for a single poll, keyed (poll ID, delegator) and (poll ID, delegate, delegator).
+// They take precedence over DELEGATIONS
+pub const POLL_DELEGATIONS: Map<(&str, Addr), Addr> = Map::new("poll_delegations");
+pub const POLL_DELEGATORS: Map<(&str, Addr, Addr), ()> = Map::new("poll_delegators");
+
+// The delegators whose weight is part of a delegate's ballot, keyed (poll ID, delegator)
+pub const DELEGATED_VOTES: Map<(&str, Addr), DelegatedVote> = Map::new("delegated_votes");
+
+// The same keyed (delegator, poll ID) so the polls a delegator's weight was cast in can be found.
+// Must be kept in sync with DELEGATED_VOTES
+pub const DELEGATOR_VOTES: Map<(Addr, &str), ()> = Map::new("delegator_votes");
+
+// The instant-runoff count of a closed ranked poll, run in batches by TallyRanked so deciding
+// the poll never has to read every ballot once per round in a single transaction
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RankedTally {
+    // The round being counted, only options still in the running are listed
+    pub tallies: Vec<PollOption>,
+    // The last voter whose ballot was counted in this round
+    pub counted_to: Option<Addr>,
+    // Set once a round ended without eliminating anyone, `tallies` is then the final round
+    pub finished: bool,
+}
+
+pub const RANKED_TALLIES: Map<&str, RankedTally> = Map::new("ranked_tallies");
+
+// And keyed (poll ID, delegate, delegator) so the weight a delegate picked up can be dropped
+// without reading every delegated vote on the poll. Must be kept in sync with DELEGATED_VOTES
+pub const DELEGATE_VOTES: Map<(&str, Addr, Addr), ()> = Map::new("delegate_votes");
@@ -0,0 +1,92 @@
+// Checks on user supplied poll text, against the limits the admin set in Config
+
+use crate::state::{PollKind, PollLimits};
+use crate::ContractError;
+
+// Poll IDs end up in storage keys and URLs so are kept to a safe set of characters
+pub fn validate_poll_id(poll_id: &str, limits: &PollLimits) -> Result<(), ContractError> {
+    let valid_chars = poll_id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if poll_id.is_empty() || !valid_chars {
+        return Err(ContractError::InvalidPollId {});
+    }
+    check_length("poll_id", poll_id, limits.max_id_length)
+}
+
+// Trims the question and makes sure something is left
+pub fn normalize_question(question: &str, limits: &PollLimits) -> Result<String, ContractError> {
+    let question = question.trim();
+    if question.is_empty() {
+        return Err(ContractError::EmptyQuestion {});
+    }
+    check_length("question", question, limits.max_question_length)?;
+    Ok(question.to_string())
+}
+
+// Trims every option, they must be distinct once trimmed
+pub fn normalize_options(
+    options: &[String],
+    limits: &PollLimits,
+) -> Result<Vec<String>, ContractError> {
+    if options.len() < limits.min_options as usize {
+        return Err(ContractError::TooFewOptions {
+            min: limits.min_options,
+        });
+    }
+    if options.len() > limits.max_options as usize {
+        return Err(ContractError::TooManyOptions {});
+    }
+
+    let mut normalized: Vec<String> = Vec::with_capacity(options.len());
+    for option in options {
+        let option = option.trim();
+        if option.is_empty() {
+            return Err(ContractError::EmptyOption {});
+        }
+        check_length("option", option, limits.max_option_length)?;
+        if normalized.iter().any(|o| o == option) {
+            return Err(ContractError::DuplicateOption {
+                option: option.to_string(),
+            });
+        }
+        normalized.push(option.to_string());
+    }
+    Ok(normalized)
+}
+
+// The kind's settings have to work with the number of options
+pub fn validate_kind(kind: &PollKind, options: usize) -> Result<(), ContractError> {
+    match kind {
+        PollKind::Approval { max_choices }
+            if *max_choices == 0 || *max_choices as usize > options =>
+        {
+            Err(ContractError::InvalidMaxChoices {})
+        }
+        PollKind::Quadratic { credits: 0 } => Err(ContractError::InvalidCredits {}),
+        _ => Ok(()),
+    }
+}
+
+// The admin cannot set limits no poll could ever meet
+pub fn validate_limits(limits: &PollLimits) -> Result<(), ContractError> {
+    if limits.min_options < 2
+        || limits.max_options < limits.min_options
+        || limits.max_id_length == 0
+        || limits.max_question_length == 0
+        || limits.max_option_length == 0
+    {
+        return Err(ContractError::InvalidLimits {});
+    }
+    Ok(())
+}
+
+fn check_length(field: &str, value: &str, max: u32) -> Result<(), ContractError> {
+    if value.chars().count() > max as usize {
+        return Err(ContractError::TooLong {
+            field: field.to_string(),
+            max,
+        });
+    }
+    Ok(())
+}